@@ -0,0 +1,42 @@
+//! Selector definitions used while scraping, see [`scraping::selectors`]
+
+use anyhow::{Context, Result};
+use scraping::selectors::{self, Definitions};
+
+static FILENAME: &str = "selectors.toml";
+
+/// Install the embedded selectors, overridden by the latest published selectors
+/// and then by a `selectors.toml` in the current working directory
+pub async fn load() -> Result<()> {
+    let mut definitions = Definitions::defaults();
+
+    #[cfg(feature = "updater")]
+    match crate::versioning::fetch_selectors().await {
+        // selectors only a newer release knows are skipped, the rest still applies
+        Ok(latest) => match definitions.merge_known(&latest) {
+            Ok(skipped) => {
+                for name in skipped {
+                    eprintln!("Onbekende selector '{name}' in nieuwste selectors overgeslagen");
+                }
+            }
+            Err(err) => eprintln!("Nieuwste selectors zijn ongeldig en worden overgeslagen: {err}"),
+        },
+        Err(err) => eprintln!("Nieuwste selectors konden niet worden opgehaald: {err:?}"),
+    }
+
+    let file_path = std::env::current_dir()
+        .context("valid current working directory")?
+        .join(FILENAME);
+
+    if file_path.exists() {
+        let data = std::fs::read_to_string(file_path)?;
+        definitions
+            .merge(&data)
+            .with_context(|| format!("bestand '{FILENAME}' is ongeldig"))?;
+    }
+
+    if selectors::install(definitions).is_err() {
+        anyhow::bail!("selectors are already in use");
+    }
+    Ok(())
+}
@@ -1,5 +1,14 @@
 // mod config;
-use scraping::{self, providers::Provider, status::Status};
+mod definitions;
+mod settings;
+use scraping::{
+    self,
+    fetch::{Fetcher, HttpFetcher},
+    limit::{LimitConfig, LimitedFetcher, Limiter},
+    providers::{Pages, Provider, QueryResult},
+    retry::{RetryFetcher, RetryPolicy},
+    status::{self, Status},
+};
 #[cfg(feature = "updater")]
 mod versioning;
 
@@ -8,20 +17,103 @@ use crossterm::{cursor, terminal, ExecutableCommand, QueueableCommand};
 use rust_xlsxwriter::Workbook;
 use std::{
     io::{self, BufRead, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     str::FromStr,
+    sync::Arc,
+    time::Duration,
 };
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(long)]
     ask_location: bool,
 
     #[arg(long)]
     location: Option<PathBuf>,
+
+    /// Maximum amount of requests at the same time
+    #[arg(long)]
+    max_concurrent: Option<usize>,
+
+    /// Maximum amount of requests per second to a single shop, 0 disables the limit
+    #[arg(long)]
+    requests_per_second: Option<f64>,
+
+    /// Pause between two requests to the same shop in milliseconds
+    #[arg(long)]
+    delay_ms: Option<u64>,
+
+    /// Text file with a product link on every line, scraped instead of a listing
+    #[arg(long)]
+    urls: Option<PathBuf>,
+
+    /// Keep sponsored results, marked in the sponsored column, instead of leaving them out
+    #[arg(long)]
+    sponsored: bool,
+
+    /// Shorten texts such as descriptions to at most this many characters
+    #[arg(long)]
+    max_text_length: Option<usize>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Check whether the selectors still match the pages of every shop,
+    /// exits with status 1 when they don't
+    Check,
+}
+
+impl Cli {
+    /// Command line arguments take precedence over the settings file
+    fn limit_config(&self, settings: &settings::Scraping) -> LimitConfig {
+        let default = LimitConfig::default();
+        let requests_per_second = self
+            .requests_per_second
+            .or(settings.requests_per_second)
+            .or(default.requests_per_second);
+
+        LimitConfig {
+            max_concurrent: self
+                .max_concurrent
+                .or(settings.max_concurrent)
+                .unwrap_or(default.max_concurrent),
+            requests_per_second: requests_per_second.filter(|rps| *rps > 0.0),
+            delay: self
+                .delay_ms
+                .or(settings.delay_ms)
+                .map(Duration::from_millis)
+                .unwrap_or(default.delay),
+        }
+    }
+
+    fn fetcher(&self, settings: &settings::Scraping, state: status::State) -> Arc<dyn Fetcher> {
+        let limiter = Arc::new(Limiter::new(self.limit_config(settings)));
+        let fetcher = LimitedFetcher::new(HttpFetcher::default(), limiter);
+        let fetcher = RetryFetcher::new(fetcher, RetryPolicy::default(), state);
+        Arc::new(fetcher)
+    }
+}
+
+/// Run the health check of every shop and print the reports
+async fn check(fetcher: Arc<dyn Fetcher>, state: status::State) -> Result<()> {
+    let mut healthy = true;
+    for check in scraping::check::defaults() {
+        let report = check.run(fetcher.as_ref(), &state).await;
+        println!("{report}");
+        healthy &= report.is_healthy();
+    }
+
+    if !healthy {
+        eprintln!("Niet alle selectors komen overeen, zie het rapport hierboven");
+        std::process::exit(1);
+    }
+    Ok(())
 }
 
 fn read_line(msg: &str) -> std::io::Result<String> {
@@ -30,12 +122,50 @@ fn read_line(msg: &str) -> std::io::Result<String> {
     stdin.lines().next().expect("input")
 }
 
+/// Product links from a text file, empty lines and lines starting with `#` are skipped
+fn read_urls(path: &Path) -> Result<Vec<String>> {
+    let urls = std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    if urls.is_empty() {
+        anyhow::bail!("geen links gevonden in {}", path.display());
+    }
+    Ok(urls)
+}
+
+/// Either an amount of pages, a range like `11-20` or `alle`
+fn parse_pages(input: &str) -> Pages {
+    let input = input.trim().to_lowercase();
+    if let Some((first, last)) = input.split_once("-") {
+        if let (Ok(first), Ok(last)) = (first.trim().parse(), last.trim().parse()) {
+            return Pages::Range { first, last };
+        }
+    }
+
+    match input.as_str() {
+        "alle" | "all" => Pages::All,
+        count => Pages::Count(count.parse().unwrap_or(1)),
+    }
+}
+
 static OUTFILE: &str = "products.xlsx";
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     // let conf = config::read()?;
+    let settings = settings::read()?;
+
+    if let Some(Command::Check) = cli.command {
+        definitions::load().await?;
+        let state = Arc::new(Status::default());
+        let fetcher = cli.fetcher(&settings.scraping, state.clone());
+        return check(fetcher, state).await;
+    }
 
     let state = Status::new(|status| {
         let mut stdout = io::stdout();
@@ -54,14 +184,57 @@ async fn main() -> Result<()> {
         )
     }
 
-    let url = read_line("Link naar zoekresultaten")?;
-    let pages = read_line("Hoeveel paginas? (1)")?.parse().unwrap_or(1);
+    definitions::load().await?;
+    let fetcher = cli.fetcher(&settings.scraping, state.clone());
+
+    let result = match cli.urls {
+        Some(ref path) => {
+            let urls = read_urls(path)?;
+            // the list may mix shops, every link is scraped by the provider of its own shop
+            let providers = urls
+                .iter()
+                .map(|url| {
+                    Ok(Provider::from_url(url)?
+                        .include_sponsored(cli.sponsored)
+                        .max_text_length(cli.max_text_length))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let queries = providers.iter().zip(&urls).map(|(provider, url)| {
+                let url = std::slice::from_ref(url);
+                provider.query_product_urls(url, fetcher.clone(), state.clone())
+            });
+
+            let mut result = QueryResult::default();
+            for query in futures::future::join_all(queries).await {
+                result.extend(query);
+            }
+            result
+        }
+        None => {
+            let url = read_line("Link naar zoekresultaten")?;
+            let pages = parse_pages(&read_line(
+                "Hoeveel paginas? (1, 'alle' of een reeks zoals 11-20)",
+            )?);
 
-    let provider = Provider::from_url(&url)?;
-    let products = provider.query_products(&url, pages, state).await?;
+            let provider = Provider::from_url(&url)?
+                .include_sponsored(cli.sponsored)
+                .max_text_length(cli.max_text_length);
+            provider.query_products(&url, pages, fetcher, state).await
+        }
+    };
 
     let mut workbook = Workbook::new();
-    workbook.push_worksheet(products.as_worksheet()?);
+    workbook.push_worksheet(result.products.as_worksheet()?);
+    if result.products.iter().any(|p| !p.specifications.is_empty()) {
+        workbook.push_worksheet(result.products.specifications_worksheet()?);
+    }
+    if !result.failures.is_empty() {
+        println!(
+            "{} fouten opgetreden, zie het blad 'fouten' voor details",
+            result.failures.len()
+        );
+        workbook.push_worksheet(result.failures.as_worksheet()?);
+    }
 
     println!("Output excel sheet gereed...");
 
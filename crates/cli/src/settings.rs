@@ -0,0 +1,37 @@
+//! Optional user settings, read from `config.toml` in the current working directory
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+static FILENAME: &str = "config.toml";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub scraping: Scraping,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Scraping {
+    /// Maximum amount of requests at the same time
+    pub max_concurrent: Option<usize>,
+    /// Maximum amount of requests per second to a single shop, 0 disables the limit
+    pub requests_per_second: Option<f64>,
+    /// Pause between two requests to the same shop in milliseconds
+    pub delay_ms: Option<u64>,
+}
+
+/// Read settings, missing settings file results in the defaults
+pub fn read() -> Result<Settings> {
+    let file_path = std::env::current_dir()
+        .context("valid current working directory")?
+        .join(FILENAME);
+
+    if !file_path.exists() {
+        return Ok(Settings::default());
+    }
+
+    let data = std::fs::read_to_string(file_path)?;
+    toml::from_str(&data).with_context(|| format!("bestand '{FILENAME}' is ongeldig"))
+}
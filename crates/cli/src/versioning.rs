@@ -6,7 +6,7 @@ use bytes::Bytes;
 use lazy_static::lazy_static;
 use reqwest::header::{ACCEPT, USER_AGENT};
 use serde::{Deserialize, Serialize};
-use std::{cmp::Ordering, fmt::Display};
+use std::{cmp::Ordering, fmt::Display, time::Duration};
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Version {
@@ -66,6 +66,9 @@ lazy_static! {
 
 static PKG_NAME: &str = "bol-scraper-empire";
 
+/// How long fetching the latest selectors may take before the embedded ones are used
+const SELECTORS_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Release {
     pub tag_name: String,
@@ -119,6 +122,21 @@ async fn fetch_latest_bin() -> Result<Bytes> {
     Ok(res.bytes().await?)
 }
 
+/// Selectors from the main branch, so broken selectors can be fixed without a new release
+pub async fn fetch_selectors() -> Result<String> {
+    let url = format!(
+        "https://raw.githubusercontent.com/{}/main/crates/scraping/selectors.toml",
+        *REPO
+    );
+    // this runs on every launch, a slow connection shouldn't keep the program from starting
+    let client = reqwest::Client::builder()
+        .timeout(SELECTORS_TIMEOUT)
+        .build()?;
+    let res = client.get(url).send().await?.error_for_status()?;
+
+    Ok(res.text().await?)
+}
+
 pub async fn try_update() -> Result<bool> {
     let latest = &latest_release().await?;
     let version = Version::parse(&latest.tag_name)?;
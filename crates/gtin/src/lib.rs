@@ -0,0 +1,130 @@
+//! Global Trade Item Numbers (EAN, UPC) as the exact digits printed on the product.
+//!
+//! Keeping them as text preserves leading zeros, e.g. a UPC padded to 13 digits.
+
+use std::{fmt::Display, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GtinError {
+    #[error("'{0}' is not a digit")]
+    InvalidCharacter(char),
+
+    #[error("a GTIN has 8, 12, 13 or 14 digits, not {0}")]
+    InvalidLength(usize),
+
+    #[error("check digit should be {expected}, not {found}")]
+    InvalidCheckDigit { expected: u8, found: u8 },
+}
+
+/// The length of a GTIN, which decides where it is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GtinKind {
+    /// EAN-8, for small packages
+    Gtin8,
+    /// UPC-A, used in north america
+    Gtin12,
+    /// EAN-13, the usual barcode in europe
+    Gtin13,
+    /// Trade units such as boxes of products
+    Gtin14,
+}
+
+impl GtinKind {
+    /// Amount of digits, including the check digit
+    pub fn digits(&self) -> usize {
+        match self {
+            Self::Gtin8 => 8,
+            Self::Gtin12 => 12,
+            Self::Gtin13 => 13,
+            Self::Gtin14 => 14,
+        }
+    }
+
+    fn from_len(len: usize) -> Option<Self> {
+        match len {
+            8 => Some(Self::Gtin8),
+            12 => Some(Self::Gtin12),
+            13 => Some(Self::Gtin13),
+            14 => Some(Self::Gtin14),
+            _ => None,
+        }
+    }
+}
+
+/// A GTIN with a valid GS1 check digit
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Gtin(String);
+
+impl Gtin {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn kind(&self) -> GtinKind {
+        GtinKind::from_len(self.0.len()).expect("length to be checked when parsing")
+    }
+
+    /// The same number as 13 digits, the form most european shops expect.
+    /// Shorter ones are padded with zeros, a GTIN-14 only fits when it starts with a zero
+    pub fn to_gtin13(&self) -> Option<Gtin> {
+        match self.kind() {
+            // the check digit is counted from the right, so it stays valid
+            GtinKind::Gtin14 => self.0.strip_prefix('0').map(|gtin| Gtin(gtin.to_string())),
+            _ => Some(Gtin(format!("{:0>13}", self.0))),
+        }
+    }
+}
+
+/// GS1 check digit of the digits before it: every other digit from the right is weighted 3
+fn check_digit(digits: &[u8]) -> u8 {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, digit)| *digit as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    ((10 - sum % 10) % 10) as u8
+}
+
+impl FromStr for Gtin {
+    type Err = GtinError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let digits = s
+            .chars()
+            .map(|c| {
+                c.to_digit(10)
+                    .map(|d| d as u8)
+                    .ok_or(GtinError::InvalidCharacter(c))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if GtinKind::from_len(digits.len()).is_none() {
+            return Err(GtinError::InvalidLength(digits.len()));
+        }
+
+        let (found, payload) = digits.split_last().expect("length to be checked");
+        let expected = check_digit(payload);
+        if expected != *found {
+            return Err(GtinError::InvalidCheckDigit {
+                expected,
+                found: *found,
+            });
+        }
+
+        Ok(Self(s.to_string()))
+    }
+}
+
+impl Display for Gtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for Gtin {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
@@ -0,0 +1,51 @@
+use gtin::{Gtin, GtinError, GtinKind};
+
+#[test]
+fn parse() {
+    for (text, kind) in [
+        ("96385074", GtinKind::Gtin8),
+        ("012345678905", GtinKind::Gtin12),
+        ("8710103974734", GtinKind::Gtin13),
+        ("18710103974731", GtinKind::Gtin14),
+    ] {
+        let gtin = text.parse::<Gtin>().unwrap();
+        assert_eq!(gtin.kind(), kind, "{text}");
+        assert_eq!(gtin.as_str(), text);
+    }
+}
+
+#[test]
+fn keeps_leading_zeros() {
+    let gtin = " 0012345678905 ".parse::<Gtin>().unwrap();
+    assert_eq!(gtin.to_string(), "0012345678905");
+    assert_eq!(gtin.kind(), GtinKind::Gtin13);
+
+    let upc = "012345678905".parse::<Gtin>().unwrap();
+    assert_eq!(upc.to_gtin13(), Some(gtin.clone()));
+
+    let case = "00012345678905".parse::<Gtin>().unwrap();
+    assert_eq!(case.to_gtin13(), Some(gtin));
+    // the packaging indicator doesn't fit in 13 digits
+    let case = "18710103974731".parse::<Gtin>().unwrap();
+    assert_eq!(case.to_gtin13(), None);
+}
+
+#[test]
+fn invalid() {
+    assert_eq!(
+        "8710103974735".parse::<Gtin>(),
+        Err(GtinError::InvalidCheckDigit {
+            expected: 4,
+            found: 5
+        })
+    );
+    assert_eq!(
+        "87101039747".parse::<Gtin>(),
+        Err(GtinError::InvalidLength(11))
+    );
+    assert_eq!(
+        "8710103-74734".parse::<Gtin>(),
+        Err(GtinError::InvalidCharacter('-'))
+    );
+    assert_eq!("".parse::<Gtin>(), Err(GtinError::InvalidLength(0)));
+}
@@ -0,0 +1,246 @@
+//! Health check for the selectors of every shop.
+//!
+//! Fetches a known listing and product page per shop and reports which selectors still match,
+//! so layout changes are noticed before a real scrape fails halfway through.
+
+use crate::{
+    error::ScrapeError,
+    fetch::{fetch_dom, Fetcher},
+    providers::{amazon::Amazon, bol::Bol, Html, Product, ShopProvider, Stage},
+    selectors, status,
+};
+use std::fmt::Display;
+
+/// Pages to check for a single shop
+pub struct Check {
+    /// Name of the shop in the selector definitions
+    pub shop: &'static str,
+    pub provider: Box<dyn ShopProvider>,
+    pub listing_url: String,
+    pub product_url: String,
+    /// Selectors that should match on the listing page
+    pub listing_selectors: &'static [&'static str],
+    /// Selectors that should match on the product page
+    pub product_selectors: &'static [&'static str],
+}
+
+/// Checks for every builtin shop
+pub fn defaults() -> Vec<Check> {
+    vec![
+        Check {
+            shop: "bol",
+            provider: Box::new(Bol),
+            listing_url: "https://www.bol.com/nl/nl/l/elektrische-tandenborstels/12477/".into(),
+            product_url:
+                "https://www.bol.com/nl/nl/p/philips-sonicare-protectiveclean-4300-hx6800-63/9300000134521378/"
+                    .into(),
+            listing_selectors: &["results_count", "container", "image", "title", "price"],
+            product_selectors: &[
+                "specs_container",
+                "specs_subtitle",
+                "specs_section",
+                "specs_title",
+                "specs_value",
+            ],
+        },
+        Check {
+            shop: "amazon",
+            provider: Box::new(Amazon),
+            listing_url: "https://www.amazon.nl/s?k=elektrische+tandenborstel".into(),
+            product_url: "https://www.amazon.nl/dp/B09NXCHZ61".into(),
+            listing_selectors: &[
+                "container",
+                "image",
+                "title_wrapper",
+                "title",
+                "price_whole",
+                "price_fraction",
+            ],
+            product_selectors: &["product_title", "asin", "bullets", "details_table"],
+        },
+    ]
+}
+
+impl Check {
+    pub async fn run(&self, fetcher: &dyn Fetcher, state: &status::State) -> Report {
+        let listing = match fetch_dom(fetcher, &self.listing_url).await {
+            Ok(doc) => self.check_listing(&doc, state),
+            Err(err) => PageReport::failed(&self.listing_url, Stage::ListingPage, err),
+        };
+        let product = match fetch_dom(fetcher, &self.product_url).await {
+            Ok(doc) => self.check_product(&doc),
+            Err(err) => PageReport::failed(&self.product_url, Stage::ProductPage, err),
+        };
+
+        Report {
+            shop: self.shop,
+            pages: vec![listing, product],
+        }
+    }
+
+    fn check_listing(&self, doc: &Html, state: &status::State) -> PageReport {
+        let mut report = PageReport::new(&self.listing_url, Stage::ListingPage);
+        report.selectors = self.match_selectors(doc, self.listing_selectors);
+
+        if self.provider.is_blocked(doc) {
+            report.error = Some(ScrapeError::Blocked {
+                url: self.listing_url.clone(),
+            });
+            return report;
+        }
+
+        match self.provider.parse_listing(state, &self.listing_url, doc) {
+            Ok(listing) if listing.products.is_empty() => {
+                report.error = Some(ScrapeError::parse("products", "none found"));
+            }
+            Ok(listing) => report.fields = field_counts(&listing.products),
+            Err(err) => report.error = Some(err),
+        }
+
+        report
+    }
+
+    fn check_product(&self, doc: &Html) -> PageReport {
+        let mut report = PageReport::new(&self.product_url, Stage::ProductPage);
+        report.selectors = self.match_selectors(doc, self.product_selectors);
+
+        let mut product = Product {
+            url: self.product_url.clone(),
+            ..Default::default()
+        };
+        match self.provider.enrich_product(&mut product, doc) {
+            Ok(()) => report.fields = field_counts(&[product]),
+            Err(err) => report.error = Some(err),
+        }
+
+        report
+    }
+
+    fn match_selectors(&self, doc: &Html, names: &[&'static str]) -> Vec<SelectorMatch> {
+        names
+            .iter()
+            .map(|name| SelectorMatch {
+                name,
+                matches: doc.select(selectors::get(self.shop, name)).count(),
+            })
+            .collect()
+    }
+}
+
+/// Amount of products that have each field filled in
+fn field_counts(products: &[Product]) -> Vec<(&'static str, usize)> {
+    let count = |filled: fn(&Product) -> bool| products.iter().filter(|p| filled(p)).count();
+
+    vec![
+        ("products", products.len()),
+        ("title", count(|p| !p.title.is_empty())),
+        ("image", count(|p| !p.image.is_empty())),
+        ("url", count(|p| !p.url.is_empty())),
+        ("price", count(|p| !p.price.is_zero())),
+        ("list price", count(|p| p.list_price.is_some())),
+        ("ean", count(|p| p.ean.is_some())),
+        ("sku", count(|p| p.sku.is_some())),
+        ("brand", count(|p| p.brand.is_some())),
+        ("description", count(|p| p.description.is_some())),
+        ("features", count(|p| !p.features.is_empty())),
+        ("specifications", count(|p| !p.specifications.is_empty())),
+        ("rating", count(|p| p.rating.is_some())),
+        ("reviews", count(|p| p.review_count.is_some())),
+        ("availability", count(|p| p.availability.is_some())),
+        ("delivery", count(|p| p.delivery.is_some())),
+        ("seller", count(|p| p.seller.is_some())),
+        ("categories", count(|p| !p.categories.is_empty())),
+    ]
+}
+
+#[derive(Debug)]
+pub struct SelectorMatch {
+    pub name: &'static str,
+    /// Amount of elements the selector matched
+    pub matches: usize,
+}
+
+#[derive(Debug)]
+pub struct PageReport {
+    pub url: String,
+    pub stage: Stage,
+    pub selectors: Vec<SelectorMatch>,
+    /// Amount of parsed products that have each field filled in
+    pub fields: Vec<(&'static str, usize)>,
+    /// Why the page couldn't be fetched or parsed
+    pub error: Option<ScrapeError>,
+}
+
+impl PageReport {
+    fn new(url: &str, stage: Stage) -> Self {
+        Self {
+            url: url.to_string(),
+            stage,
+            selectors: Vec::new(),
+            fields: Vec::new(),
+            error: None,
+        }
+    }
+
+    fn failed(url: &str, stage: Stage, error: ScrapeError) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::new(url, stage)
+        }
+    }
+
+    /// The page could be parsed and every selector matched
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none() && self.selectors.iter().all(|s| s.matches > 0)
+    }
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub shop: &'static str,
+    pub pages: Vec<PageReport>,
+}
+
+impl Report {
+    pub fn is_healthy(&self) -> bool {
+        self.pages.iter().all(PageReport::is_healthy)
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = |healthy| if healthy { "ok" } else { "FAILED" };
+        writeln!(f, "{}: {}", self.shop, status(self.is_healthy()))?;
+
+        for page in &self.pages {
+            writeln!(
+                f,
+                "  {} {}: {}",
+                page.stage,
+                page.url,
+                status(page.is_healthy())
+            )?;
+            for selector in &page.selectors {
+                let source = selectors::active()
+                    .definition(self.shop, selector.name)
+                    .map(|definition| definition.source.as_str())
+                    .unwrap_or_default();
+                writeln!(
+                    f,
+                    "    {} {:<24} {:>3} matches  {source}",
+                    if selector.matches > 0 { "+" } else { "-" },
+                    selector.name,
+                    selector.matches,
+                )?;
+            }
+            for (field, count) in &page.fields {
+                writeln!(f, "      {field:<24} {count:>3}")?;
+            }
+            if let Some(err) = &page.error {
+                writeln!(f, "    error: {err}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use reqwest::StatusCode;
+use std::fmt::Display;
+
+pub type Result<T, E = ScrapeError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while scraping,
+/// callers can use this to decide whether to skip, retry or abort.
+#[derive(Debug, thiserror::Error)]
+pub enum ScrapeError {
+    /// The request could not be completed
+    #[error("network error while fetching {url}: {source}")]
+    Network {
+        url: String,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The shop responded, but not with a page we can use
+    #[error("got status {status} while fetching {url}")]
+    Status { url: String, status: StatusCode },
+
+    /// The shop served a captcha or bot detection page instead of the requested page
+    #[error("blocked by the shop while fetching {url}")]
+    Blocked { url: String },
+
+    /// A selector that should always match on this kind of page did not,
+    /// most likely the shop changed their page
+    #[error("page layout changed, nothing matched selector '{selector}'")]
+    LayoutChanged { selector: String },
+
+    /// None of the registered providers knows how to scrape this url
+    #[error("unsupported url: {url}")]
+    UnsupportedUrl { url: String },
+
+    /// A single product could not be parsed
+    #[error("unable to parse {field}: {reason}")]
+    Parse { field: String, reason: String },
+
+    /// Scraping the page crashed, most likely a bug in the parser
+    #[error("scraping the page crashed: {reason}")]
+    Panicked { reason: String },
+}
+
+impl ScrapeError {
+    pub fn network(
+        url: impl Into<String>,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        Self::Network {
+            url: url.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn layout(selector: impl Into<String>) -> Self {
+        Self::LayoutChanged {
+            selector: selector.into(),
+        }
+    }
+
+    pub fn parse(field: impl Into<String>, reason: impl Display) -> Self {
+        Self::Parse {
+            field: field.into(),
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Whether trying again later could succeed
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Network { .. } => true,
+            Self::Status { status, .. } => {
+                status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Selector definitions that can't be used, see [`crate::selectors`]
+#[derive(Debug, thiserror::Error)]
+pub enum DefinitionsError {
+    #[error("invalid selector definitions: {0}")]
+    Toml(Box<toml::de::Error>),
+
+    #[error("no selectors are defined for shop '{shop}'")]
+    UnknownShop { shop: String },
+
+    #[error("unknown selector '{name}' for shop '{shop}'")]
+    UnknownSelector { shop: String, name: String },
+
+    #[error("selector '{name}' for shop '{shop}' is invalid: {reason}")]
+    InvalidSelector {
+        shop: String,
+        name: String,
+        reason: String,
+    },
+}
+
+/// Text that doesn't look like a price
+#[derive(Debug, thiserror::Error)]
+#[error("'{0}' is not a valid price")]
+pub struct ParseMoneyError(pub String);
+
+pub trait OptionExt<T> {
+    /// Treat a missing value as a changed page layout,
+    /// `selector` is the name of the definition in `selectors.toml` that didn't match
+    fn or_layout(self, selector: &str) -> Result<T>;
+
+    /// Treat a missing value as a product that can't be parsed
+    fn or_missing(self, field: &str) -> Result<T>;
+}
+
+impl<T> OptionExt<T> for Option<T> {
+    fn or_layout(self, selector: &str) -> Result<T> {
+        self.ok_or_else(|| ScrapeError::layout(selector))
+    }
+
+    fn or_missing(self, field: &str) -> Result<T> {
+        self.ok_or_else(|| ScrapeError::parse(field, "missing"))
+    }
+}
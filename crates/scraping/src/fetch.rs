@@ -0,0 +1,180 @@
+//! Fetching of pages, abstracted so scraping can run against something else than the internet
+
+use crate::error::{Result, ScrapeError};
+use futures::future::BoxFuture;
+use reqwest::{header::HeaderMap, StatusCode};
+use scraper::Html;
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: StatusCode, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: HeaderMap::new(),
+            body: body.into(),
+        }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new(StatusCode::OK, body)
+    }
+
+    pub fn not_found() -> Self {
+        Self::new(StatusCode::NOT_FOUND, "")
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+}
+
+/// Source of pages, takes an url and responds with its status and body
+pub trait Fetcher: Send + Sync {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>>;
+}
+
+/// Fetch a page and parse it, any status other than 200 is treated as an error
+pub async fn fetch_dom(fetcher: &dyn Fetcher, url: &str) -> Result<Html> {
+    let res = fetcher.fetch(url).await?;
+    if res.status != StatusCode::OK {
+        return Err(ScrapeError::Status {
+            url: url.to_string(),
+            status: res.status,
+        });
+    }
+
+    Ok(Html::parse_document(&res.text()))
+}
+
+/// Fetches pages over the internet
+#[derive(Debug, Default, Clone)]
+pub struct HttpFetcher {
+    client: reqwest::Client,
+}
+
+impl HttpFetcher {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Fetcher for HttpFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let network_error = |err| ScrapeError::network(url, err);
+
+            let res = self.client.get(url).send().await.map_err(network_error)?;
+            let status = res.status();
+            let headers = res.headers().clone();
+            let body = res.bytes().await.map_err(network_error)?.to_vec();
+
+            Ok(Response {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
+
+/// Serves saved pages from disk, urls without a route respond with 404
+#[derive(Debug, Default, Clone)]
+pub struct FileFetcher {
+    root: PathBuf,
+    routes: HashMap<String, PathBuf>,
+}
+
+impl FileFetcher {
+    /// Routes are resolved relative to `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            routes: HashMap::new(),
+        }
+    }
+
+    pub fn route(mut self, url: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.routes.insert(url.into(), path.into());
+        self
+    }
+}
+
+impl Fetcher for FileFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let path = match self.routes.get(url) {
+                Some(path) => self.root.join(path),
+                None => return Ok(Response::not_found()),
+            };
+
+            let body = tokio::fs::read(&path)
+                .await
+                .map_err(|err| ScrapeError::network(url, err))?;
+
+            Ok(Response::ok(body))
+        })
+    }
+}
+
+/// In-memory stand-in server, useful for tests.
+///
+/// Every url has a queue of responses, the last response keeps being served once the others are used up.
+/// Urls without responses respond with 404.
+#[derive(Debug, Default)]
+pub struct MockFetcher {
+    responses: Mutex<HashMap<String, VecDeque<Response>>>,
+    requests: Mutex<Vec<String>>,
+}
+
+impl MockFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a response for the given url
+    pub fn respond(self, url: impl Into<String>, response: Response) -> Self {
+        self.responses
+            .lock()
+            .expect("responses lock")
+            .entry(url.into())
+            .or_default()
+            .push_back(response);
+        self
+    }
+
+    /// All urls that have been requested so far, in order
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().expect("requests lock").clone()
+    }
+}
+
+impl Fetcher for MockFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            self.requests
+                .lock()
+                .expect("requests lock")
+                .push(url.to_string());
+
+            let mut responses = self.responses.lock().expect("responses lock");
+            let response = match responses.get_mut(url) {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            };
+
+            Ok(response.unwrap_or_else(Response::not_found))
+        })
+    }
+}
@@ -1,2 +1,12 @@
+pub mod check;
+pub mod error;
+pub mod fetch;
+pub mod limit;
+pub mod money;
+pub mod offer;
 pub mod providers;
+pub mod retry;
+pub mod selectors;
 pub mod status;
+pub mod structured;
+pub mod text;
@@ -0,0 +1,129 @@
+//! Limits on how hard we hit a shop, to avoid getting blocked
+
+use crate::{
+    error::Result,
+    fetch::{Fetcher, Response},
+};
+use futures::future::BoxFuture;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{
+    sync::{Semaphore, SemaphorePermit},
+    time::Instant,
+};
+
+#[derive(Debug, Clone)]
+pub struct LimitConfig {
+    /// Maximum amount of requests in flight at the same time, across all hosts
+    pub max_concurrent: usize,
+    /// Maximum amount of requests per second to a single host, unlimited when `None`
+    pub requests_per_second: Option<f64>,
+    /// Minimum pause between two requests to the same host
+    pub delay: Duration,
+}
+
+impl Default for LimitConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 8,
+            requests_per_second: Some(4.0),
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+impl LimitConfig {
+    /// Time between the start of two requests to the same host
+    fn interval(&self) -> Duration {
+        let rate = match self.requests_per_second {
+            Some(rps) if rps > 0.0 => Duration::from_secs_f64(1.0 / rps),
+            _ => Duration::ZERO,
+        };
+
+        rate.max(self.delay)
+    }
+}
+
+/// Shared limiter, every request has to acquire a permit before being sent
+#[derive(Debug)]
+pub struct Limiter {
+    config: LimitConfig,
+    permits: Semaphore,
+    /// Earliest moment the next request to a host may start
+    hosts: Mutex<HashMap<String, Instant>>,
+}
+
+impl Limiter {
+    pub fn new(config: LimitConfig) -> Self {
+        Self {
+            permits: Semaphore::new(config.max_concurrent.max(1)),
+            hosts: Mutex::new(HashMap::new()),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &LimitConfig {
+        &self.config
+    }
+
+    /// Wait until a request to `url` is allowed, the request may be sent as long as the permit is held
+    pub async fn acquire(&self, url: &str) -> SemaphorePermit<'_> {
+        // the host slot is only reserved once a permit is held, otherwise requests whose slot
+        // passed while waiting for a permit would all start at once
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("semaphore is never closed");
+
+        let interval = self.config.interval();
+        if !interval.is_zero() {
+            let host = reqwest::Url::parse(url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_default();
+
+            // reserve the next free slot for this host
+            let start = {
+                let mut hosts = self.hosts.lock().expect("hosts lock");
+                let now = Instant::now();
+                let next = hosts.entry(host).or_insert(now);
+                let start = (*next).max(now);
+                *next = start + interval;
+                start
+            };
+
+            tokio::time::sleep_until(start).await;
+        }
+
+        permit
+    }
+}
+
+/// Wraps a fetcher so every request goes through a shared [`Limiter`]
+pub struct LimitedFetcher<F> {
+    inner: F,
+    limiter: Arc<Limiter>,
+}
+
+impl<F: Fetcher> LimitedFetcher<F> {
+    pub fn new(inner: F, limiter: Arc<Limiter>) -> Self {
+        Self { inner, limiter }
+    }
+
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+}
+
+impl<F: Fetcher> Fetcher for LimitedFetcher<F> {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let _permit = self.limiter.acquire(url).await;
+            self.inner.fetch(url).await
+        })
+    }
+}
@@ -0,0 +1,149 @@
+//! Prices as exact decimal amounts, parsed from the way shops write them.
+
+use crate::error::ParseMoneyError;
+use rust_decimal::prelude::ToPrimitive;
+use std::{fmt::Display, str::FromStr};
+
+pub use rust_decimal::Decimal;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Currency {
+    #[default]
+    Eur,
+    Gbp,
+    Usd,
+}
+
+impl Currency {
+    /// ISO 4217 code, e.g. `EUR`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Eur => "EUR",
+            Self::Gbp => "GBP",
+            Self::Usd => "USD",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Eur => "€",
+            Self::Gbp => "£",
+            Self::Usd => "$",
+        }
+    }
+
+    /// Currency from either its code or symbol
+    pub fn parse(text: &str) -> Option<Self> {
+        [Self::Eur, Self::Gbp, Self::Usd]
+            .into_iter()
+            .find(|currency| {
+                text.eq_ignore_ascii_case(currency.code()) || text == currency.symbol()
+            })
+    }
+
+    /// Currency mentioned anywhere in a price
+    fn find(text: &str) -> Option<Self> {
+        [Self::Eur, Self::Gbp, Self::Usd]
+            .into_iter()
+            .find(|currency| text.contains(currency.symbol()) || text.contains(currency.code()))
+    }
+
+    /// Excel number format that shows amounts in this currency
+    pub fn excel_format(&self) -> String {
+        format!("\"{}\" #,##0.00", self.symbol())
+    }
+}
+
+impl Display for Currency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// An amount of money in a specific currency
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Money {
+    pub amount: Decimal,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn new(amount: Decimal, currency: Currency) -> Self {
+        Self { amount, currency }
+    }
+
+    /// Parse a price like `€ 1.299,00`, `1,299.00`, `54,-` or `£12.50`.
+    ///
+    /// Both Dutch/German and English notation are understood, the decimal separator is
+    /// whichever of `,` or `.` comes last unless it's followed by exactly three digits.
+    /// `currency` is used when the text doesn't mention one.
+    pub fn parse(text: &str, currency: Currency) -> Result<Self, ParseMoneyError> {
+        let invalid = || ParseMoneyError(text.to_string());
+        let currency = Currency::find(text).unwrap_or(currency);
+
+        let trimmed = text
+            .trim()
+            .trim_end_matches(['-', '–', '—'])
+            .trim_end_matches([',', '.']);
+        let number = trimmed
+            .chars()
+            .filter(|c| c.is_ascii_digit() || matches!(c, ',' | '.'))
+            .collect::<String>();
+        if !number.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let amount = match number.rfind([',', '.']) {
+            Some(index) if is_decimal_separator(&number, index) => {
+                let (whole, fraction) = number.split_at(index);
+                format!("{}.{}", digits(whole), &fraction[1..])
+            }
+            _ => digits(&number),
+        };
+
+        let amount = Decimal::from_str(&amount).map_err(|_| invalid())?;
+        Ok(Self::new(amount, currency))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.amount.is_zero()
+    }
+
+    /// Amount as a float, only meant for output like spreadsheets
+    pub fn to_f64(&self) -> f64 {
+        self.amount.to_f64().unwrap_or_default()
+    }
+}
+
+fn digits(text: &str) -> String {
+    text.chars().filter(char::is_ascii_digit).collect()
+}
+
+/// Whether the separator at `index` separates the cents instead of thousands
+fn is_decimal_separator(number: &str, index: usize) -> bool {
+    let separator = number.as_bytes()[index];
+    let other = if separator == b',' { '.' } else { ',' };
+    // when both are used the last one is always the decimal separator
+    if number.contains(other) {
+        return true;
+    }
+
+    let repeated = number.matches(separator as char).count() > 1;
+    let groups_of_thousands = number.len() - index - 1 == 3 && !number.starts_with("0");
+    !repeated && !groups_of_thousands
+}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    /// Parse a price, euros are assumed when no currency is mentioned
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text, Currency::default())
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {:.2}", self.currency.symbol(), self.amount)
+    }
+}
@@ -0,0 +1,234 @@
+//! Whether a product can be ordered and when it arrives, read from the texts shops show for it.
+//!
+//! Shops only show these as text in the language of the page,
+//! so the keywords of every supported language are listed here.
+
+use chrono::{Datelike, NaiveDate};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Availability {
+    InStock,
+    /// Can be ordered, but isn't released or restocked yet
+    PreOrder,
+    OutOfStock,
+}
+
+const OUT_OF_STOCK: &[&str] = &[
+    "uitverkocht",
+    "niet leverbaar",
+    "niet op voorraad",
+    "niet beschikbaar",
+    "out of stock",
+    "unavailable",
+    "épuisé",
+    "rupture de stock",
+    "indisponible",
+    "nicht verfügbar",
+    "ausverkauft",
+];
+const PRE_ORDER: &[&str] = &[
+    "pre-order",
+    "preorder",
+    "vooruitbestellen",
+    "reserveer",
+    "verwacht",
+    "précommande",
+    "vorbestell",
+];
+const IN_STOCK: &[&str] = &[
+    "op voorraad",
+    "in huis",
+    "in stock",
+    "en stock",
+    "auf lager",
+];
+
+impl Availability {
+    /// Availability described by a stock or delivery text, e.g. "Tijdelijk uitverkocht".
+    /// A text promising a delivery means the product is in stock
+    pub fn from_text(text: &str) -> Option<Self> {
+        let text = text.to_lowercase();
+        let contains = |keywords: &[&str]| keywords.iter().any(|keyword| text.contains(keyword));
+
+        if contains(OUT_OF_STOCK) {
+            Some(Self::OutOfStock)
+        } else if contains(PRE_ORDER) {
+            Some(Self::PreOrder)
+        } else if contains(IN_STOCK) || DeliveryEstimate::parse(&text).is_some() {
+            Some(Self::InStock)
+        } else {
+            None
+        }
+    }
+
+    /// Availability from schema.org structured data, with or without the prefix, e.g. `InStock`
+    pub fn from_schema_org(availability: &str) -> Option<Self> {
+        let availability = availability.rsplit('/').next()?;
+        match availability {
+            "InStock" | "LimitedAvailability" | "OnlineOnly" | "InStoreOnly" => Some(Self::InStock),
+            "PreOrder" | "PreSale" | "BackOrder" => Some(Self::PreOrder),
+            "OutOfStock" | "SoldOut" | "Discontinued" => Some(Self::OutOfStock),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Availability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::InStock => "in stock",
+            Self::PreOrder => "pre-order",
+            Self::OutOfStock => "out of stock",
+        })
+    }
+}
+
+/// The delivery promise as the shop shows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Delivery {
+    pub text: String,
+    pub estimate: Option<DeliveryEstimate>,
+}
+
+/// When the delivery is expected, either relative to the day the page was scraped or a date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryEstimate {
+    Days(u32),
+    /// A date without a year, e.g. "zo 20 okt"
+    Date {
+        month: u32,
+        day: u32,
+    },
+}
+
+impl Delivery {
+    pub fn parse(text: &str) -> Self {
+        Self {
+            text: text.to_string(),
+            estimate: DeliveryEstimate::parse(text),
+        }
+    }
+
+    /// Amount of days from `today` until the delivery
+    pub fn days(&self, today: NaiveDate) -> Option<u32> {
+        match self.estimate? {
+            DeliveryEstimate::Days(days) => Some(days),
+            DeliveryEstimate::Date { month, day } => {
+                // a date before today is in the next year, e.g. in december for a delivery in january
+                let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+                let date = match date < today {
+                    true => NaiveDate::from_ymd_opt(today.year() + 1, month, day)?,
+                    false => date,
+                };
+                Some((date - today).num_days() as u32)
+            }
+        }
+    }
+}
+
+/// Words for a delivery in the next days
+const RELATIVE_DAYS: &[(&str, u32)] = &[
+    ("overmorgen", 2),
+    ("übermorgen", 2),
+    ("après-demain", 2),
+    ("vandaag", 0),
+    ("today", 0),
+    ("aujourd'hui", 0),
+    ("heute", 0),
+    ("morgen", 1),
+    ("tomorrow", 1),
+    ("demain", 1),
+];
+
+/// Words for the moment an order has to be placed, e.g. "vandaag besteld, morgen in huis",
+/// the delivery day follows them
+const ORDER_CUTOFFS: &[&str] = &["besteld", "commandé", "ordered", "bestellt"];
+
+/// Month names and their abbreviations in dutch, english, french and german, by month
+const MONTHS: [&[&str]; 12] = [
+    &["jan", "janv", "januari", "january", "janvier", "januar"],
+    &[
+        "feb", "fév", "févr", "februari", "february", "février", "februar",
+    ],
+    &["mrt", "mar", "mär", "maart", "march", "mars", "märz"],
+    &["apr", "avr", "april", "avril"],
+    &["mei", "may", "mai"],
+    &["jun", "juni", "june", "juin"],
+    &["jul", "juil", "juli", "july", "juillet"],
+    &["aug", "augustus", "august", "août"],
+    &["sep", "sept", "september", "septembre"],
+    &["okt", "oct", "oktober", "october", "octobre"],
+    &["nov", "november", "novembre"],
+    &["dec", "déc", "dez", "december", "décembre", "dezember"],
+];
+
+lazy_static! {
+    /// An amount of days, the last one of a range, e.g. "uiterlijk 3 dagen" or "2-4 werkdagen"
+    static ref days_regex: Regex = Regex::new(
+        r"(?i)(\d+)(?:\s*[-–]\s*(\d+))?\s+(?:werk)?(?:dagen|dag|days|day|jours|jour|tagen|tage|tag)\b"
+    )
+    .unwrap();
+    /// A day followed by a month, e.g. "20 okt" or "20. Oktober"
+    static ref day_month_regex: Regex = Regex::new(r"\b(\d{1,2})\.?\s+(\p{L}{3,})\b").unwrap();
+    /// A month followed by a day, e.g. "October 20"
+    static ref month_day_regex: Regex = Regex::new(r"\b(\p{L}{3,})\.?\s+(\d{1,2})\b").unwrap();
+}
+
+/// Number of the month, only whole names or known abbreviations count so "maanden" isn't march
+fn month(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    MONTHS
+        .iter()
+        .position(|names| names.contains(&name.as_str()))
+        .map(|month| month as u32 + 1)
+}
+
+impl DeliveryEstimate {
+    pub fn parse(text: &str) -> Option<Self> {
+        let lowercase = text.to_lowercase();
+        let after_cutoff = ORDER_CUTOFFS
+            .iter()
+            .filter_map(|cutoff| lowercase.rfind(cutoff).map(|i| i + cutoff.len()))
+            .max()
+            .unwrap_or(0);
+        // the last word wins, at the same end the longest so "overmorgen" isn't taken for "morgen"
+        let relative_days = |text: &str| {
+            RELATIVE_DAYS
+                .iter()
+                .filter_map(|(word, days)| {
+                    text.rfind(word)
+                        .map(|i| (i + word.len(), word.len(), *days))
+                })
+                .max()
+        };
+        if let Some((_, _, days)) =
+            relative_days(&lowercase[after_cutoff..]).or_else(|| relative_days(&lowercase))
+        {
+            return Some(Self::Days(days));
+        }
+
+        if let Some(captures) = days_regex.captures(text) {
+            let days = captures.get(2).unwrap_or(captures.get(1)?);
+            return days.as_str().parse().ok().map(Self::Days);
+        }
+
+        let date = |day: &str, month_name: &str| {
+            let day = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+            Some(Self::Date {
+                month: month(month_name)?,
+                day,
+            })
+        };
+        day_month_regex
+            .captures_iter(text)
+            .find_map(|captures| date(&captures[1], &captures[2]))
+            .or_else(|| {
+                month_day_regex
+                    .captures_iter(text)
+                    .find_map(|captures| date(&captures[2], &captures[1]))
+            })
+    }
+}
@@ -1,141 +1,406 @@
 use super::*;
+use crate::{selectors, structured};
 
 const RESULTS_PER_PAGE: usize = 56;
 
-pub async fn query_products(url: &str, pages: usize, state: status::State) -> Result<Products> {
-    let mut handles = Vec::with_capacity(pages);
-
-    for i in 0..pages {
-        let url = url.to_owned();
-        let state = state.clone();
-        let handle = tokio::spawn(async move {
-            println!("querying page {}", i + 1);
-            state.add_pending();
-            let url = paginate_url(&url, i + 1);
-            let doc = fetch_dom(&url).await.expect("valid dom");
-            state.pending_success();
-            parse_products(&state, doc)
-        });
+pub struct Amazon;
+
+fn selector(name: &str) -> &'static Selector {
+    selectors::get("amazon", name)
+}
+
+/// A country specific amazon shop, they all share the same page layout
+#[derive(Debug)]
+pub struct Marketplace {
+    /// Top level domain, e.g. `co.uk`
+    pub domain: &'static str,
+    pub currency: Currency,
+}
+
+pub const MARKETPLACES: &[Marketplace] = &[
+    Marketplace {
+        domain: "nl",
+        currency: Currency::Eur,
+    },
+    Marketplace {
+        domain: "com.be",
+        currency: Currency::Eur,
+    },
+    Marketplace {
+        domain: "de",
+        currency: Currency::Eur,
+    },
+    Marketplace {
+        domain: "fr",
+        currency: Currency::Eur,
+    },
+    Marketplace {
+        domain: "co.uk",
+        currency: Currency::Gbp,
+    },
+    Marketplace {
+        domain: "com",
+        currency: Currency::Usd,
+    },
+];
+
+impl Marketplace {
+    pub fn from_url(url: &str) -> Option<&'static Self> {
+        let parts = domain_parts(url)?;
+        let (shop, domain) = parts.split_first()?;
+        if *shop != "amazon" {
+            return None;
+        }
+
+        let domain = domain.join(".");
+        MARKETPLACES.iter().find(|m| m.domain == domain)
+    }
+
+    /// Url that the links on the marketplace's pages are relative to
+    pub fn base_url(&self) -> String {
+        format!("https://amazon.{}", self.domain)
+    }
+}
+
+impl ShopProvider for Amazon {
+    fn matches_url(&self, url: &str) -> bool {
+        Marketplace::from_url(url).is_some()
+    }
+
+    fn is_blocked(&self, doc: &Html) -> bool {
+        doc.select(selector("captcha")).next().is_some()
+    }
+
+    fn parse_listing(&self, state: &status::State, url: &str, doc: &Html) -> Result<Listing> {
+        let marketplace = Marketplace::from_url(url).unwrap_or(&MARKETPLACES[0]);
+        parse_products(state, marketplace, doc)
+    }
+
+    fn enriches_products(&self) -> bool {
+        true
+    }
 
-        handles.push(handle);
+    fn enrich_product(&self, product: &mut Product, doc: &Html) -> Result<()> {
+        parse_product_page(product, doc)
     }
 
-    let results = futures::future::join_all(handles)
-        .await
-        .into_iter()
-        .flat_map(|res| res.unwrap())
-        .collect::<Vec<_>>();
+    fn page_count(&self, doc: &Html) -> Option<usize> {
+        // amazon limits the amount of pages, so the pagination is more accurate than the results count
+        let last_page = doc
+            .select(selector("pagination_item"))
+            .filter_map(|item| item.text().collect::<String>().trim().parse().ok())
+            .max();
+        if last_page.is_some() {
+            return last_page;
+        }
+
+        let info = doc.select(selector("result_info")).next()?;
+        let info = info.text().collect::<String>();
+        let captures = results_count_regex.captures(&info)?;
+        let per_page = parse_count(&captures[1])?;
+        let results = parse_count(&captures[2])?;
 
-    Ok(Products(results))
+        Some(pages_for_results(results, per_page))
+    }
 }
 
-fn parse_products(state: &status::State, doc: Html) -> Vec<Product> {
-    let container = doc.select(&container_selector).next().unwrap();
+fn parse_products(state: &status::State, marketplace: &Marketplace, doc: &Html) -> Result<Listing> {
+    let Some(container) = doc.select(selector("container")).next() else {
+        return structured_listing(state, doc, &marketplace.base_url()).or_layout("container");
+    };
 
-    let mut buffer = Vec::with_capacity(RESULTS_PER_PAGE);
+    let mut listing = Listing {
+        products: Vec::with_capacity(RESULTS_PER_PAGE),
+        failures: Vec::new(),
+    };
     for element in container.child_elements() {
         match element.attr("data-component-type") {
             Some("s-search-result") => {
-                if parse_product(state, element, &mut buffer).is_err() {
-                    continue;
+                if let Err(err) = parse_product(state, marketplace, element, &mut listing.products)
+                {
+                    listing.failures.push(err);
                 }
             }
             _ => continue,
         }
     }
 
-    buffer
-}
-
-lazy_static! {
-    static ref container_selector: Selector =
-        Selector::parse(".s-main-slot.s-result-list.s-search-results").unwrap();
-    static ref image_selector: Selector = Selector::parse(".s-image").unwrap();
-    static ref title_wrapper_selector: Selector =
-        Selector::parse(".s-title-instructions-style a").unwrap();
-    static ref title_selector: Selector = Selector::parse("span").unwrap();
-    static ref price_whole_selector: Selector = Selector::parse(".a-price-whole").unwrap();
-    static ref price_fraction_selector: Selector = Selector::parse(".a-price-fraction").unwrap();
-    static ref price_old_selector: Selector = Selector::parse(".a-price.a-text-price").unwrap();
+    Ok(listing)
 }
 
 fn parse_product(
     state: &status::State,
+    marketplace: &Marketplace,
     el: ElementRef<'_>,
     buffer: &mut Vec<Product>,
 ) -> Result<()> {
     let image = el
-        .select(&image_selector)
+        .select(selector("image"))
         .next()
-        .context("product image")?
+        .or_missing("image")?
         .attr("src")
-        .context("product image source")?;
+        .or_missing("image source")?;
     let title_wrapper = el
-        .select(&title_wrapper_selector)
+        .select(selector("title_wrapper"))
         .next()
-        .context("a title wrapper")?;
+        .or_missing("title wrapper")?;
     let title = title_wrapper
-        .select(&title_selector)
+        .select(selector("title"))
         .next()
-        .context("a title")?
-        .inner_html();
-
-    if sponsored_regex.is_match(&title) {
-        anyhow::bail!("Sponsored product");
-    }
+        .map(collapsed_text)
+        .or_missing("title")?;
 
-    let url = title_wrapper.attr("href").context("product to have url")?;
+    let href = title_wrapper.attr("href").or_missing("url")?;
+    let sponsored = is_sponsored(el, href);
+    let url = format!("{}{}", marketplace.base_url(), href);
+    // ads link to a tracking url with the product page in its query
+    let url = if sponsored {
+        sponsored_target(&url).unwrap_or(url)
+    } else {
+        url
+    };
 
-    for item in buffer.iter() {
+    for item in buffer.iter_mut() {
         if item.url == url {
+            // an ad for a product that is also listed organically
+            item.sponsored &= sponsored;
             state.add_duplicate();
-            anyhow::bail!("Product with same url already parsed")
+            return Ok(());
         }
     }
 
-    let price = match el.select(&price_old_selector).next() {
-        Some(price) => price
-            .child_elements()
-            .nth(1)
-            .unwrap()
-            .inner_html()
-            .strip_prefix("€")
-            .context("price to have euro symbol prefix")?
-            .to_string(),
-        None => {
-            let price_whole = el
-                .select(&price_whole_selector)
-                .next()
-                .context("Expected a whole price")?
-                .text()
-                .next()
-                .context("Expected a whole price")?;
-            let price_fraction = el
-                .select(&price_fraction_selector)
-                .next()
-                .context("Expected a price fraction")?
-                .inner_html();
+    let price_whole = el
+        .select(selector("price_whole"))
+        .next()
+        .or_missing("whole price")?
+        .text()
+        .next()
+        .or_missing("whole price")?;
+    let price_fraction = el
+        .select(selector("price_fraction"))
+        .next()
+        .or_missing("price fraction")?
+        .inner_html();
+    // the thousands separator differs per marketplace, so only the digits of the whole part are kept
+    let price_whole = price_whole
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+    let price = Money::parse(
+        &format!("{}.{}", price_whole, price_fraction),
+        marketplace.currency,
+    )
+    .map_err(|err| ScrapeError::parse("price", err))?;
 
-            format!("{},{}", price_whole, price_fraction)
+    // the struck through price shown next to discounted products
+    let list_price = match el.select(selector("price_old")).next() {
+        Some(list_price) => {
+            let list_price = list_price
+                .child_elements()
+                .nth(1)
+                .or_missing("list price")?
+                .inner_html();
+            let list_price = Money::parse(&list_price, marketplace.currency)
+                .map_err(|err| ScrapeError::parse("list price", err))?;
+            Some(list_price)
         }
+        None => None,
     };
-    let price: f64 = price
-        .replace(",", ".")
-        .parse()
-        .context("Expected valid parsable floating point price")?;
+
+    let rating = el
+        .select(selector("rating"))
+        .next()
+        .and_then(|rating| parse_rating(&collapsed_text(rating)));
+    let review_count = el
+        .select(selector("review_count"))
+        .next()
+        .and_then(|count| parse_count(&collapsed_text(count)))
+        .and_then(|count| count.try_into().ok());
+
+    let delivery = el
+        .select(selector("delivery"))
+        .next()
+        .map(|delivery| Delivery::parse(&collapsed_text(delivery)));
+    let availability = delivery
+        .as_ref()
+        .and_then(|delivery| Availability::from_text(&delivery.text));
 
     let product = Product {
         title,
         price,
+        list_price,
         image: image.to_string(),
-        url: format!("https://amazon.nl{}", url),
-        ean: None,
+        url,
+        sponsored,
+        availability,
+        delivery,
+        rating,
+        review_count,
+        ..Default::default()
     };
 
     buffer.push(product);
     Ok(())
 }
 
+/// Ads are recognised by their markup rather than the label, which is translated on every marketplace
+fn is_sponsored(el: ElementRef<'_>, href: &str) -> bool {
+    el.value().classes().any(|class| class == "AdHolder")
+        || el.select(selector("sponsored_label")).next().is_some()
+        || href.starts_with("/sspa/")
+}
+
+/// The product page an ad's tracking url redirects to
+fn sponsored_target(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let (_, target) = url.query_pairs().find(|(name, _)| name == "url")?;
+    absolute_url(url.as_str(), &target)
+}
+
 lazy_static! {
-    static ref sponsored_regex: Regex = Regex::new(r"Gesponsord").unwrap();
+    /// Matches the results shown on the page and the total, e.g. "1-48 van meer dan 3.000 resultaten".
+    /// French pages group thousands with a space, e.g. "1-48 sur plus de 3 000 résultats"
+    static ref results_count_regex: Regex = Regex::new(r"\d+-(\d+)\D+(\d[\d.,\s]*)").unwrap();
+    static ref asin_url_regex: Regex = Regex::new(r"/dp/([A-Z0-9]{10})").unwrap();
+}
+
+/// Add the details from a product page: ASIN, EAN, brand, bullet points, technical details and description
+pub fn parse_product_page(product: &mut Product, doc: &Html) -> Result<()> {
+    let structured = structured::product(doc);
+    if doc.select(selector("product_title")).next().is_none() {
+        // the page isn't recognised anymore, the structured data is all that's left
+        structured.or_layout("product_title")?.fill(product);
+        product.categories = structured::breadcrumbs(doc);
+        return Ok(());
+    }
+
+    let specifications = parse_details(doc);
+
+    let sku = doc
+        .select(selector("asin"))
+        .next()
+        .and_then(|input| input.attr("value"))
+        .map(str::to_string)
+        .or_else(|| specifications.get("ASIN").map(str::to_string))
+        .or_else(|| {
+            let captures = asin_url_regex.captures(&product.url)?;
+            Some(captures[1].to_string())
+        });
+
+    // multiple EAN codes can be listed, the first one belongs to the product itself
+    let ean = ["EAN", "GTIN"]
+        .iter()
+        .find_map(|name| specifications.get(name))
+        .and_then(|ean| ean.split(',').next())
+        .map(|ean| {
+            ean.trim()
+                .parse()
+                .map_err(|err| ScrapeError::parse("EAN", err))
+        })
+        .transpose()?;
+
+    let brand = doc
+        .select(selector("brand"))
+        .next()
+        .map(collapsed_text)
+        .or_else(|| {
+            ["Merk", "Brand", "Marke", "Marque"]
+                .iter()
+                .find_map(|name| specifications.get(name))
+                .map(str::to_string)
+        });
+
+    let features = doc
+        .select(selector("bullets"))
+        .map(collapsed_text)
+        .filter(|bullet| !bullet.is_empty())
+        .collect();
+
+    let description = doc
+        .select(selector("description"))
+        .next()
+        .map(collapsed_text)
+        .filter(|description| !description.is_empty());
+
+    product.sku = sku;
+    product.ean = ean.or(product.ean.take());
+    product.brand = brand;
+    product.features = features;
+    product.description = description;
+    product.specifications = specifications;
+    if product.rating.is_none() {
+        product.rating = doc
+            .select(selector("product_rating"))
+            .next()
+            .and_then(|rating| rating.attr("title"))
+            .and_then(parse_rating);
+        product.review_count = doc
+            .select(selector("product_review_count"))
+            .next()
+            .and_then(|count| parse_count(&collapsed_text(count)))
+            .and_then(|count| count.try_into().ok());
+    }
+    parse_offer(product, doc);
+    product.categories = doc
+        .select(selector("breadcrumbs"))
+        .map(collapsed_text)
+        .filter(|category| !category.is_empty())
+        .collect();
+    if product.categories.is_empty() {
+        product.categories = structured::breadcrumbs(doc);
+    }
+    if let Some(structured) = structured {
+        structured.fill(product);
+    }
+
+    Ok(())
+}
+
+/// Stock, delivery promise and seller from the buy box of a product page
+fn parse_offer(product: &mut Product, doc: &Html) {
+    if let Some(availability) = doc.select(selector("availability")).next() {
+        product.availability = Availability::from_text(&collapsed_text(availability));
+    }
+    if let Some(delivery) = doc.select(selector("product_delivery")).next() {
+        product.delivery = Some(Delivery::parse(&collapsed_text(delivery)));
+    }
+    if let Some(seller) = doc.select(selector("seller")).next() {
+        let seller = collapsed_text(seller);
+        // e.g. "Amazon", "Amazon.nl" or "Amazon EU S.à r.l."
+        product.sold_by_platform = Some(seller.starts_with("Amazon"));
+        product.seller = Some(seller);
+    }
+}
+
+/// Technical details and additional information, amazon uses either tables or a bullet list for these
+fn parse_details(doc: &Html) -> Specifications {
+    let mut specifications = Specifications::default();
+
+    for table in doc.select(selector("details_table")) {
+        let section = table
+            .parent()
+            .and_then(ElementRef::wrap)
+            .and_then(|column| column.select(selector("details_section_title")).next())
+            .map(collapsed_text)
+            .unwrap_or_default();
+
+        for row in table.select(selector("details_row")) {
+            let name = row.select(selector("details_name")).next();
+            let value = row.select(selector("details_value")).next();
+            if let (Some(name), Some(value)) = (name, value) {
+                specifications.insert(&section, collapsed_text(name), collapsed_text(value));
+            }
+        }
+    }
+
+    for item in doc.select(selector("detail_bullets")) {
+        let mut spans = item.select(selector("detail_bullets_part"));
+        if let (Some(name), Some(value)) = (spans.next(), spans.next()) {
+            let name = collapsed_text(name);
+            let name = name.trim_end_matches([':', ' ']);
+            specifications.insert("", name, collapsed_text(value));
+        }
+    }
+
+    specifications
 }
@@ -1,150 +1,176 @@
 use super::*;
+use crate::{selectors, structured};
 
 const RESULTS_PER_PAGE: usize = 24;
 
-async fn query_specifications(product: Product, state: &status::Status) -> Product {
-    state.add_pending();
-    match query_product_page(&product.url).await {
-        Ok(specifications) => {
-            state.pending_success();
-            Product {
-                title: product.title,
-                image: product.image,
-                price: product.price,
-                url: product.url,
-                ean: Some(specifications.ean),
-            }
-        }
-        Err(err) => {
-            eprintln!("Error while trying to query product page: {err:?}");
-            state.pending_errored();
-            product
-        }
+pub struct Bol;
+
+fn selector(name: &str) -> &'static Selector {
+    selectors::get("bol", name)
+}
+
+/// Labels of the EAN specification, bol uses the same page for dutch and french
+const EAN_LABELS: &[&str] = &["EAN", "Code EAN", "EAN-code"];
+const BRAND_LABELS: &[&str] = &["Merk", "Marque"];
+/// Text in front of the seller's name, e.g. "Verkoop door bol"
+const SELLER_PREFIXES: &[&str] = &["Verkoop door", "Vendu par"];
+/// Selectors every product row in a listing has to match
+const ROW_SELECTORS: &[&str] = &["image", "title", "price"];
+
+/// Country and language of the bol shop, taken from the start of the path, e.g. `/be/fr/`
+pub fn locale_from_url(url: &str) -> Locale {
+    let mut path = url.split('/').skip(3);
+    match (path.next(), path.next()) {
+        (Some("be"), Some("fr")) => Locale::new("fr", "BE"),
+        (Some("be"), _) => Locale::new("nl", "BE"),
+        _ => Locale::new("nl", "NL"),
     }
 }
 
-pub async fn query_products(
-    url: &str,
-    pages: usize,
-    state: status::State,
-) -> Result<Products> {
-    let mut handles = Vec::with_capacity(pages);
-
-    for i in 0..pages {
-        let url = url.to_owned();
-        let state = state.clone();
-        let handle = tokio::spawn(async move {
-            println!("querying page {}", i + 1);
-            state.add_pending();
-            let url = paginate_url(&url, i + 1);
-            let doc = fetch_dom(&url).await.expect("valid dom");
-
-            let products = parse_products(&state, doc);
-            state.pending_success();
-
-            let with_specifications = products
-                .into_iter()
-                .map(|p| query_specifications(p, &state))
-                .collect::<Vec<_>>();
-
-            futures::future::join_all(with_specifications).await
-        });
+impl ShopProvider for Bol {
+    fn matches_url(&self, url: &str) -> bool {
+        matches!(domain_parts(url).as_deref(), Some(["bol", "com" | "nl"]))
+    }
+
+    fn parse_listing(&self, state: &status::State, url: &str, doc: &Html) -> Result<Listing> {
+        let mut listing = parse_products(state, doc)?;
+
+        let locale = locale_from_url(url);
+        for product in listing.products.iter_mut() {
+            product.locale = Some(locale);
+        }
 
-        handles.push(handle);
+        Ok(listing)
     }
 
-    let results = futures::future::join_all(handles)
-        .await
-        .into_iter()
-        .flat_map(|res| res.unwrap())
-        .collect::<Vec<_>>();
+    fn page_count(&self, doc: &Html) -> Option<usize> {
+        let results = doc.select(selector("results_count")).next()?;
+        let results = parse_count(&results.text().collect::<String>())?;
+        Some(pages_for_results(results, RESULTS_PER_PAGE))
+    }
 
-    Ok(results.into())
-}
+    fn enriches_products(&self) -> bool {
+        true
+    }
 
-async fn query_product_page(url: &str) -> Result<Specifications> {
-    let doc = fetch_dom(url).await?;
-    parse_product_page(doc)
+    fn enrich_product(&self, product: &mut Product, doc: &Html) -> Result<()> {
+        let structured = structured::product(doc);
+        let specifications = match parse_product_page(doc) {
+            Ok(specifications) => specifications,
+            // the EAN can still be found in the structured data
+            Err(ScrapeError::LayoutChanged { selector }) if structured.is_some() => {
+                eprintln!("falling back to structured data, {selector} not found");
+                Specifications::default()
+            }
+            Err(err) => return Err(err),
+        };
+        let ean = EAN_LABELS
+            .iter()
+            .find_map(|label| specifications.get(label))
+            .map(|ean| ean.parse().map_err(|err| ScrapeError::parse("EAN", err)))
+            .transpose();
+        let brand = BRAND_LABELS
+            .iter()
+            .find_map(|label| specifications.get(label))
+            .map(str::to_string);
+
+        product.specifications = specifications;
+        product.ean = ean?;
+        product.brand = brand.or(product.brand.take());
+        product.locale = Some(locale_from_url(&product.url));
+        if product.rating.is_none() {
+            (product.rating, product.review_count) = parse_stars(doc.root_element());
+        }
+        // the product page is more up to date than the listing
+        parse_offer(doc.root_element(), product);
+        product.categories = parse_breadcrumbs(doc);
+        if product.categories.is_empty() {
+            product.categories = structured::breadcrumbs(doc);
+        }
+        if let Some(structured) = structured {
+            structured.fill(product);
+        }
+        product.ean.as_ref().or_missing("EAN")?;
+        Ok(())
+    }
 }
 
-fn parse_products(state: &status::State, doc: Html) -> Vec<Product> {
-    let container = doc.select(&container_selector).next().expect("Pagina komt niet overeen met de verwachte structuur. Deze is nog niet toegevoegd, of bol.com heeft hun pagina aangepast");
+fn parse_products(state: &status::State, doc: &Html) -> Result<Listing> {
+    let Some(container) = doc.select(selector("container")).next() else {
+        return structured_listing(state, doc, "https://bol.com").or_layout("container");
+    };
 
-    let mut buffer = Vec::with_capacity(RESULTS_PER_PAGE);
+    let mut listing = Listing {
+        products: Vec::with_capacity(RESULTS_PER_PAGE),
+        failures: Vec::new(),
+    };
+    let mut rows = Vec::new();
+    let mut parsed = 0;
     for element in container.child_elements() {
-        if let Err(err) = parse_product_items(state, element, &mut buffer) {
-            eprintln!("failed to parse product listing: {err:#}")
+        rows.push(element);
+        // rows without a title, product link or price are decoration, e.g. the sponsored banner
+        if ["title", "product_link", "price"]
+            .iter()
+            .all(|name| element.select(selector(name)).next().is_none())
+        {
+            continue;
+        }
+        match parse_product_items(state, element, &mut listing.products) {
+            Ok(()) => parsed += 1,
+            Err(err) => {
+                eprintln!("failed to parse product listing: {err}");
+                listing.failures.push(err);
+            }
         }
     }
 
-    buffer
-}
-
-lazy_static! {
-    static ref specs_container_selector: Selector = Selector::parse("section[data-group-name='ProductSpecification'] .js_show-more-specifications .js_show-more-content").unwrap();
-    static ref specs_section_selector: Selector = Selector::parse(".specs__list").unwrap();
-    static ref specs_title_selector: Selector = Selector::parse(".specs__title").unwrap();
-    static ref specs_value_selector: Selector = Selector::parse(".specs__value").unwrap();
-}
+    // not a single row could be read, so the rows themselves changed
+    if !rows.is_empty() && parsed == 0 {
+        let missing = ROW_SELECTORS.iter().find(|name| {
+            rows.iter()
+                .all(|row| row.select(selector(name)).next().is_none())
+        });
+        return Err(ScrapeError::layout(*missing.unwrap_or(&"container")));
+    }
 
-#[derive(Debug)]
-pub struct Specifications {
-    pub ean: u64,
+    Ok(listing)
 }
 
-fn parse_product_page(doc: Html) -> Result<Specifications> {
-    let specs = match doc.select(&specs_container_selector).next() {
-        Some(specs) => specs,
-        None => anyhow::bail!("No specs list"),
-    };
+/// Every specification listed on a product page, in the order bol shows them
+pub fn parse_product_page(doc: &Html) -> Result<Specifications> {
+    let specs = doc
+        .select(selector("specs_container"))
+        .next()
+        .or_layout("specs_container")?;
 
-    let mut ean_code: Option<u64> = None;
+    let mut specifications = Specifications::default();
 
-    'outer: for section in specs.child_elements() {
+    for section in specs.child_elements() {
         if section.attr("class") != Some("specs") {
             continue;
         }
+        let section_title = section
+            .select(selector("specs_subtitle"))
+            .next()
+            .map(collapsed_text)
+            .unwrap_or_default();
         let section = section
-            .select(&specs_section_selector)
+            .select(selector("specs_section"))
             .next()
-            .context("Specifications wrapper in section")?;
+            .or_layout("specs_section")?;
 
         for item in section.child_elements() {
-            let spec_title = item
-                .select(&specs_title_selector)
-                .next()
-                .context("A specification title")?
-                .inner_html();
-
-            let spec_value = item
-                .select(&specs_value_selector)
-                .next()
-                .context("A specification value")?
-                .inner_html();
-
-            if spec_title.trim() == "EAN" {
-                ean_code = Some(spec_value.trim().parse()?);
-                break 'outer;
+            let title = item.select(selector("specs_title")).next();
+            let value = item.select(selector("specs_value")).next();
+
+            // rows without a title or value are decoration, e.g. a divider
+            if let (Some(title), Some(value)) = (title, value) {
+                specifications.insert(&section_title, collapsed_text(title), collapsed_text(value));
             }
         }
     }
 
-    let ean = match ean_code {
-        Some(ean) => ean,
-        None => anyhow::bail!("No EAN code found for product"),
-    };
-
-    Ok(Specifications { ean })
-}
-
-lazy_static! {
-    static ref container_selector: Selector =
-        Selector::parse(".list-view.product-list.js_multiple_basket_buttons_page").unwrap();
-    static ref image_selector: Selector = Selector::parse("img").unwrap();
-    static ref title_selector: Selector = Selector::parse(".product-title").unwrap();
-    static ref price_selector: Selector = Selector::parse(r#"meta[itemprop="price"]"#).unwrap();
-    static ref price_old_selector: Selector =
-        Selector::parse(r#"del[data-test="from-price"]"#).unwrap();
+    Ok(specifications)
 }
 
 fn parse_product_items(
@@ -153,57 +179,113 @@ fn parse_product_items(
     buffer: &mut Vec<Product>,
 ) -> Result<()> {
     // TODO: SVG IMAGES
-    let image = el.select(&image_selector).next().context("Image source")?;
+    let image = el.select(selector("image")).next().or_missing("image")?;
     let image = image.attr("src").unwrap_or(
         image
             .attr("data-src")
-            .context("either src or data-src attr for img")?,
+            .or_missing("either src or data-src attr for img")?,
     );
 
     let title = el
-        .select(&title_selector)
+        .select(selector("title"))
         .next()
-        .context("a title")?
-        .inner_html();
+        .map(collapsed_text)
+        .or_missing("title")?;
 
     let url = el
-        .select(&title_selector)
+        .select(selector("title"))
         .next()
-        .context("a title")?
+        .or_missing("title")?
         .attr("href")
-        .context("product to have url")?;
+        .or_missing("url")?;
+    let url = format!("https://bol.com{}", url);
 
     for item in buffer.iter() {
         if item.url == url {
             state.add_duplicate();
-            anyhow::bail!("Product with same url already parsed")
+            return Ok(());
         }
     }
 
-    let price = match el.select(&price_old_selector).next() {
-        Some(price) => price.inner_html(),
-        None => el
-            .select(&price_selector)
-            .next()
-            .context("Expected product to have a price")?
-            .attr("content")
-            .context("price content")?
-            .parse()
-            .context("valid f64 price")?,
-    };
-    let price: f64 = price
-        .replace(",", ".")
-        .parse()
-        .context("Expected valid parsable floating point price")?;
+    let price = el
+        .select(selector("price"))
+        .next()
+        .or_missing("price")?
+        .attr("content")
+        .or_missing("price content")?
+        .parse::<Money>()
+        .map_err(|err| ScrapeError::parse("price", err))?;
+    let list_price = el
+        .select(selector("price_old"))
+        .next()
+        .map(|price| price.inner_html().parse::<Money>())
+        .transpose()
+        .map_err(|err| ScrapeError::parse("list price", err))?;
+
+    let (rating, review_count) = parse_stars(el);
 
-    let product = Product {
+    let mut product = Product {
         title,
         price,
+        list_price,
         image: image.to_string(),
-        url: format!("https://bol.com{}", url),
-        ean: None,
+        url,
+        rating,
+        review_count,
+        ..Default::default()
     };
+    parse_offer(el, &mut product);
 
     buffer.push(product);
     Ok(())
 }
+
+/// Stars and amount of reviews, the same widget is used on listings and product pages
+fn parse_stars(el: ElementRef<'_>) -> (Option<f64>, Option<u32>) {
+    let Some(stars) = el.select(selector("rating")).next() else {
+        return (None, None);
+    };
+    let rating = stars.attr("title").and_then(parse_rating);
+    let review_count = stars
+        .attr("data-count")
+        .and_then(|count| count.parse().ok());
+    (rating, review_count)
+}
+
+/// Stock, delivery promise and seller, shown the same way on listings and product pages
+fn parse_offer(el: ElementRef<'_>, product: &mut Product) {
+    if let Some(delivery) = el.select(selector("delivery")).next() {
+        let delivery = Delivery::parse(&collapsed_text(delivery));
+        product.availability = Availability::from_text(&delivery.text);
+        // "Tijdelijk uitverkocht" is shown in the same place, but it isn't a delivery promise
+        product.delivery =
+            (product.availability != Some(Availability::OutOfStock)).then_some(delivery);
+    }
+
+    if let Some(seller) = el.select(selector("seller")).next() {
+        // partners link to their shop page, bol itself doesn't
+        let partner = seller.select(selector("seller_link")).next();
+        let name = match partner {
+            Some(link) => collapsed_text(link),
+            None => {
+                let text = collapsed_text(seller);
+                SELLER_PREFIXES
+                    .iter()
+                    .find_map(|prefix| text.strip_prefix(prefix))
+                    .map_or(text.clone(), |name| name.trim().to_string())
+            }
+        };
+        product.seller = Some(name);
+        product.sold_by_platform = Some(partner.is_none());
+    }
+}
+
+/// Category path of a product page, without the link to the home page
+fn parse_breadcrumbs(doc: &Html) -> Vec<String> {
+    doc.select(selector("breadcrumbs"))
+        .filter(|item| item.select(selector("breadcrumb_home")).next().is_none())
+        .filter_map(|item| item.select(selector("breadcrumb_label")).next())
+        .map(collapsed_text)
+        .filter(|label| !label.is_empty())
+        .collect()
+}
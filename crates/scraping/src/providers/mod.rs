@@ -1,86 +1,767 @@
 pub mod amazon;
 pub mod bol;
+pub mod schema_org;
 
-pub use anyhow::{Context, Result};
+pub use crate::error::{OptionExt, Result, ScrapeError};
 pub use lazy_static::lazy_static;
 pub use regex::Regex;
 pub use scraper::{selectable::Selectable, ElementRef, Html, Selector};
 
-use crate::status;
-use reqwest::StatusCode;
-use std::ops::{Deref, DerefMut};
+pub use crate::money::{Currency, Decimal, Money};
+pub use crate::offer::{Availability, Delivery};
+pub use gtin::Gtin;
 
-pub async fn fetch_dom(url: &str) -> Result<Html> {
-    let res = reqwest::get(url).await?;
-    if res.status() != StatusCode::OK {
-        anyhow::bail!("Error while fetching DOM, got status {:?}", res.status())
+use crate::{
+    fetch::{fetch_dom, Fetcher},
+    status, structured, text,
+};
+use rust_decimal::prelude::ToPrimitive;
+use std::{
+    fmt::Display,
+    ops::{Deref, DerefMut, RangeInclusive},
+    sync::Arc,
+};
+
+/// A webshop that products can be scraped from.
+///
+/// Implement this trait and add it to a [`Registry`] to support a new shop,
+/// fetching and scheduling is handled by [`Provider::query_products`].
+pub trait ShopProvider: Send + Sync {
+    /// Whether this provider knows how to scrape the given url
+    fn matches_url(&self, url: &str) -> bool;
+
+    /// Url of the given listing page, pages start at 1
+    fn paginate_url(&self, url: &str, page: usize) -> String {
+        paginate_url(url, page)
+    }
+
+    /// Page the given listing url points to, if it points to a specific page
+    fn start_page(&self, url: &str) -> Option<usize> {
+        page_from_url(url)
+    }
+
+    /// Whether the shop served a captcha or bot detection page instead of the requested page
+    fn is_blocked(&self, _doc: &Html) -> bool {
+        false
+    }
+
+    /// Parse all products on the listing page found at `url`,
+    /// errors are reserved for pages that can't be parsed at all
+    fn parse_listing(&self, state: &status::State, url: &str, doc: &Html) -> Result<Listing>;
+
+    /// Total amount of listing pages, detected from the first page
+    fn page_count(&self, _doc: &Html) -> Option<usize> {
+        None
+    }
+
+    /// Whether the detail page of every product should be visited after parsing the listing
+    fn enriches_products(&self) -> bool {
+        false
+    }
+
+    /// Add information from the product's detail page
+    fn enrich_product(&self, _product: &mut Product, _doc: &Html) -> Result<()> {
+        Ok(())
     }
+}
 
-    let body = res.text().await?;
+/// Split the domain of an url into its parts, leading `www` is skipped
+pub fn domain_parts(url: &str) -> Option<Vec<&str>> {
+    let domain = url.split("/").nth(2)?;
+    let parts = domain
+        .split(".")
+        .skip_while(|x| x == &"www")
+        .collect::<Vec<_>>();
 
-    Ok(Html::parse_document(&body))
+    Some(parts)
 }
 
-#[derive(clap::Subcommand, Debug)]
-pub enum Provider {
-    Amazon,
-    Bol,
+/// Collection of providers to pick from based on the url that is being scraped
+pub struct Registry {
+    providers: Vec<Provider>,
+}
+
+impl Registry {
+    /// Create a registry without any providers, see [`Registry::default`] for the builtin shops
+    pub fn new() -> Self {
+        Self {
+            providers: Vec::new(),
+        }
+    }
+
+    /// Add a provider, providers registered first take precedence
+    pub fn register(&mut self, provider: impl ShopProvider + 'static) -> &mut Self {
+        self.providers.push(Provider::new(provider));
+        self
+    }
+
+    pub fn from_url(&self, url: &str) -> Result<Provider> {
+        let unsupported = || ScrapeError::UnsupportedUrl {
+            url: url.to_string(),
+        };
+
+        if domain_parts(url).is_none() {
+            return Err(unsupported());
+        }
+
+        self.providers
+            .iter()
+            .find(|p| p.shop.matches_url(url))
+            .cloned()
+            .ok_or_else(unsupported)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry
+            .register(amazon::Amazon)
+            .register(bol::Bol)
+            .register(schema_org::SchemaOrg);
+        registry
+    }
+}
+
+/// Upper limit for the amount of pages when querying all pages
+const MAX_PAGES: usize = 500;
+
+/// Which listing pages to query.
+///
+/// [`Pages::Count`] and [`Pages::All`] start at the page in the url when it has one, otherwise at the first page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pages {
+    /// A fixed amount of pages
+    Count(usize),
+    /// The pages from `first` up to and including `last`
+    Range { first: usize, last: usize },
+    /// Every remaining page of the listing
+    All,
+}
+
+impl From<usize> for Pages {
+    fn from(value: usize) -> Self {
+        Self::Count(value)
+    }
+}
+
+/// Amount of pages needed to show `results` when every page holds `per_page` results
+pub fn pages_for_results(results: usize, per_page: usize) -> usize {
+    results.div_ceil(per_page.max(1))
+}
+
+/// Parse a number like `1.234` or `3,000`, ignoring thousand separators
+pub fn parse_count(text: &str) -> Option<usize> {
+    text.chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()
+}
+
+/// The first number in a rating text such as "4,6 van 5 sterren" or "4.6 out of 5 stars"
+pub fn parse_rating(text: &str) -> Option<f64> {
+    let rating = rating_regex.find(text)?.as_str().replace(',', ".");
+    rating
+        .parse()
+        .ok()
+        .filter(|rating| (0.0..=5.0).contains(rating))
+}
+
+lazy_static! {
+    static ref rating_regex: Regex = Regex::new(r"\d+(?:[.,]\d+)?").unwrap();
+}
+
+/// Text of an element with whitespace collapsed into single spaces,
+/// invisible text direction marks are dropped as well
+pub fn collapsed_text(el: ElementRef<'_>) -> String {
+    text::collapse(el.text())
+}
+
+/// Resolve a link found on the page at `base_url`
+pub fn absolute_url(base_url: &str, href: &str) -> Option<String> {
+    let url = reqwest::Url::parse(base_url).ok()?.join(href).ok()?;
+    Some(url.to_string())
+}
+
+/// Products described by the structured data of a listing page,
+/// used when the layout of the page is no longer recognised
+pub fn structured_listing(state: &status::State, doc: &Html, base_url: &str) -> Option<Listing> {
+    let mut listing = Listing::default();
+
+    for item in structured::products(doc) {
+        let Some(mut product) = item.to_product() else {
+            continue;
+        };
+        let Some(url) = absolute_url(base_url, &product.url) else {
+            continue;
+        };
+        product.url = url;
+
+        if listing.products.iter().any(|item| item.url == product.url) {
+            state.add_duplicate();
+            continue;
+        }
+        listing.products.push(product);
+    }
+
+    (!listing.products.is_empty()).then_some(listing)
+}
+
+/// Handle to a registered [`ShopProvider`]
+#[derive(Clone)]
+pub struct Provider {
+    shop: Arc<dyn ShopProvider>,
+    include_sponsored: bool,
+    max_text_length: Option<usize>,
 }
 
 impl Provider {
+    pub fn new(provider: impl ShopProvider + 'static) -> Self {
+        Self {
+            shop: Arc::new(provider),
+            include_sponsored: false,
+            max_text_length: None,
+        }
+    }
+
+    /// Keep sponsored results, flagged with [`Product::sponsored`], instead of dropping them
+    pub fn include_sponsored(mut self, include: bool) -> Self {
+        self.include_sponsored = include;
+        self
+    }
+
+    /// Shorten every text of a product to at most `max_chars` characters, e.g. long descriptions
+    pub fn max_text_length(mut self, max_chars: Option<usize>) -> Self {
+        self.max_text_length = max_chars;
+        self
+    }
+
+    /// Find a builtin provider for the given url
+    pub fn from_url(url: &str) -> Result<Self> {
+        Registry::default().from_url(url)
+    }
+
+    /// Query listing pages, either a fixed amount or all of them.
+    ///
+    /// Failing pages or products don't stop the query,
+    /// they are collected in [`QueryResult::failures`] next to the products that did succeed.
     pub async fn query_products(
         &self,
         url: &str,
-        pages: usize,
+        pages: impl Into<Pages>,
+        fetcher: Arc<dyn Fetcher>,
         state: status::State,
-    ) -> Result<Products> {
-        match self {
-            Self::Amazon => amazon::query_products(url, pages, state).await,
-            Self::Bol => bol::query_products(url, pages, state).await,
+    ) -> QueryResult {
+        let start = self.shop.start_page(url).unwrap_or(1).max(1);
+
+        match pages.into() {
+            Pages::Count(0) => QueryResult::default(),
+            Pages::Count(count) => {
+                let pages = start..=start + count - 1;
+                self.query_pages(url, pages, &fetcher, &state).await
+            }
+            Pages::Range { first, last } => {
+                let pages = first.max(1)..=last;
+                self.query_pages(url, pages, &fetcher, &state).await
+            }
+            Pages::All => self.query_all_pages(url, start, &fetcher, &state).await,
         }
     }
 
-    pub fn from_url(url: &str) -> Result<Self> {
-        let domain = match url.split("/").nth(2) {
-            Some(domain) => domain,
-            None => anyhow::bail!("Invalid url, unable to parse domain"),
+    /// Query the detail pages of the given products directly, without going through a listing
+    pub async fn query_product_urls(
+        &self,
+        urls: &[String],
+        fetcher: Arc<dyn Fetcher>,
+        state: status::State,
+    ) -> QueryResult {
+        let products = urls.iter().map(|url| {
+            let product = Product {
+                url: url.clone(),
+                ..Default::default()
+            };
+            self.query_product_page(fetcher.as_ref(), product, &state)
+        });
+
+        let mut result = QueryResult::default();
+        for (product, failure) in futures::future::join_all(products).await {
+            match failure {
+                // without a listing there is nothing to fall back on when the page gave no title
+                Some(failure) if product.title.is_empty() => result.failures.push(failure),
+                failure => {
+                    result.products.push(product);
+                    result.failures.extend(failure);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Query every page from `start` on, the amount of pages is detected from the first page when possible.
+    /// Otherwise pages are queried one by one until a page without products is found.
+    async fn query_all_pages(
+        &self,
+        url: &str,
+        start: usize,
+        fetcher: &Arc<dyn Fetcher>,
+        state: &status::State,
+    ) -> QueryResult {
+        let first_url = self.shop.paginate_url(url, start);
+        let (mut result, page_count) = self
+            .query_page(fetcher.as_ref(), &first_url, start, state.clone())
+            .await;
+
+        if result.products.is_empty() {
+            return result;
+        }
+
+        match page_count {
+            Some(count) => {
+                println!("{count} paginas gevonden");
+                let last = count.min(start + MAX_PAGES);
+                result.extend(
+                    self.query_pages(url, start + 1..=last, fetcher, state)
+                        .await,
+                );
+            }
+            None => {
+                for page in start + 1..=start + MAX_PAGES {
+                    let url = self.shop.paginate_url(url, page);
+                    let (page_result, _) = self
+                        .query_page(fetcher.as_ref(), &url, page, state.clone())
+                        .await;
+
+                    let empty = page_result.products.is_empty();
+                    result.extend(page_result);
+                    if empty {
+                        break;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    async fn query_pages(
+        &self,
+        url: &str,
+        pages: RangeInclusive<usize>,
+        fetcher: &Arc<dyn Fetcher>,
+        state: &status::State,
+    ) -> QueryResult {
+        let mut handles = Vec::with_capacity(pages.size_hint().0);
+
+        for page in pages {
+            let provider = self.clone();
+            let url = self.shop.paginate_url(url, page);
+            let fetcher = fetcher.clone();
+            let state = state.clone();
+            let page_url = url.clone();
+            let handle = tokio::spawn(async move {
+                provider
+                    .query_page(fetcher.as_ref(), &url, page, state)
+                    .await
+            });
+
+            handles.push(async move { (page_url, handle.await) });
+        }
+
+        let mut result = QueryResult::default();
+        for (url, res) in futures::future::join_all(handles).await {
+            match res {
+                Ok((page_result, _)) => result.extend(page_result),
+                // a bug on one page shouldn't lose the pages that did succeed
+                Err(err) => {
+                    eprintln!("Error while querying {url}: {err}");
+                    let error = ScrapeError::Panicked {
+                        reason: err.to_string(),
+                    };
+                    result
+                        .failures
+                        .push(Failure::new(&url, Stage::ListingPage, error));
+                }
+            }
+        }
+
+        result
+    }
+
+    async fn fetch_page(&self, fetcher: &dyn Fetcher, url: &str) -> Result<Html> {
+        let doc = fetch_dom(fetcher, url).await?;
+        if self.shop.is_blocked(&doc) {
+            return Err(ScrapeError::Blocked {
+                url: url.to_string(),
+            });
+        }
+
+        Ok(doc)
+    }
+
+    async fn query_page(
+        &self,
+        fetcher: &dyn Fetcher,
+        url: &str,
+        page: usize,
+        state: status::State,
+    ) -> (QueryResult, Option<usize>) {
+        println!("querying page {page}");
+        state.add_pending();
+        let listing = match self.fetch_page(fetcher, url).await {
+            Ok(doc) => self
+                .shop
+                .parse_listing(&state, url, &doc)
+                .map(|listing| (listing, self.shop.page_count(&doc))),
+            Err(err) => Err(err),
+        };
+        let (listing, page_count) = match listing {
+            Ok(listing) => {
+                state.pending_success();
+                listing
+            }
+            Err(err) => {
+                eprintln!("Error while querying page {page}: {err}");
+                state.pending_errored();
+                let result = QueryResult {
+                    failures: vec![Failure::new(url, Stage::ListingPage, err)].into(),
+                    ..Default::default()
+                };
+                return (result, None);
+            }
         };
 
-        let parts = domain
-            .split(".")
-            .skip_while(|x| x == &"www")
+        let mut products = listing.products;
+        if !self.include_sponsored {
+            products.retain(|p| !p.sponsored);
+        }
+
+        let mut failures = listing
+            .failures
+            .into_iter()
+            .map(|err| Failure::new(url, Stage::ListingItem, err))
             .collect::<Vec<_>>();
 
-        let (provider, tld) = match parts.as_slice() {
-            ["amazon", tld] => (Self::Amazon, tld),
-            ["bol", tld] => (Self::Bol, tld),
-            _ => anyhow::bail!("Unsupported url: {url}"),
+        if !self.shop.enriches_products() {
+            for product in products.iter_mut() {
+                product.normalize_text(self.max_text_length);
+            }
+            let result = QueryResult {
+                products: products.into(),
+                failures: failures.into(),
+            };
+            return (result, page_count);
+        }
+
+        let enriched = products
+            .into_iter()
+            .map(|p| self.query_product_page(fetcher, p, &state));
+
+        let mut products = Vec::with_capacity(enriched.len());
+        for (product, failure) in futures::future::join_all(enriched).await {
+            match failure {
+                // a product only known by its link has nothing to show without its page
+                Some(failure) if product.title.is_empty() => failures.push(failure),
+                failure => {
+                    products.push(product);
+                    failures.extend(failure);
+                }
+            }
+        }
+
+        let result = QueryResult {
+            products: products.into(),
+            failures: failures.into(),
+        };
+        (result, page_count)
+    }
+
+    /// Enrich the product with its detail page, the product is kept as is when this fails
+    async fn query_product_page(
+        &self,
+        fetcher: &dyn Fetcher,
+        mut product: Product,
+        state: &status::Status,
+    ) -> (Product, Option<Failure>) {
+        state.add_pending();
+        let res = match self.fetch_page(fetcher, &product.url).await {
+            Ok(doc) => self.shop.enrich_product(&mut product, &doc),
+            Err(err) => Err(err),
         };
+        product.normalize_text(self.max_text_length);
 
-        if *tld != "com" && *tld != "nl" {
-            anyhow::bail!("unsupported top level domain {tld}")
+        match res {
+            Ok(()) => {
+                state.pending_success();
+                (product, None)
+            }
+            Err(err) => {
+                eprintln!("Error while trying to query product page: {err}");
+                state.pending_errored();
+                let failure = Failure::new(&product.url, Stage::ProductPage, err);
+                (product, Some(failure))
+            }
         }
+    }
+}
+
+/// Products found on a single listing page
+#[derive(Debug, Default)]
+pub struct Listing {
+    pub products: Vec<Product>,
+    /// Products on the page that could not be parsed
+    pub failures: Vec<ScrapeError>,
+}
+
+/// Where in the scraping process a failure happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Fetching or parsing a whole listing page
+    ListingPage,
+    /// Parsing a single product on a listing page
+    ListingItem,
+    /// Fetching or parsing the detail page of a product
+    ProductPage,
+}
 
-        Ok(provider)
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::ListingPage => "listing page",
+            Self::ListingItem => "listing item",
+            Self::ProductPage => "product page",
+        })
     }
 }
 
 #[derive(Debug)]
+pub struct Failure {
+    pub url: String,
+    pub stage: Stage,
+    pub error: ScrapeError,
+}
+
+impl Failure {
+    pub fn new(url: &str, stage: Stage, error: ScrapeError) -> Self {
+        Self {
+            url: url.to_string(),
+            stage,
+            error,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Failures(Vec<Failure>);
+
+impl Failures {
+    pub fn as_worksheet(&self) -> Result<rust_xlsxwriter::Worksheet, rust_xlsxwriter::XlsxError> {
+        let mut worksheet = rust_xlsxwriter::Worksheet::new();
+        worksheet.set_name("fouten")?;
+
+        let column_names = ["url", "stage", "error"];
+        for (col, name) in column_names.iter().enumerate() {
+            worksheet.write(0, col as u16, *name)?;
+        }
+
+        for (i, failure) in self.0.iter().enumerate() {
+            let row = (i + 1) as u32;
+            worksheet.write(row, 0, &failure.url)?;
+            worksheet.write(row, 1, failure.stage.to_string())?;
+            worksheet.write(row, 2, failure.error.to_string())?;
+        }
+
+        Ok(worksheet)
+    }
+}
+
+impl From<Vec<Failure>> for Failures {
+    fn from(value: Vec<Failure>) -> Self {
+        Self(value)
+    }
+}
+
+impl Deref for Failures {
+    type Target = Vec<Failure>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Failures {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Outcome of a query, contains everything that succeeded and what went wrong
+#[derive(Debug, Default)]
+pub struct QueryResult {
+    pub products: Products,
+    pub failures: Failures,
+}
+
+impl QueryResult {
+    pub fn extend(&mut self, other: QueryResult) {
+        self.products.extend(other.products.0);
+        self.failures.extend(other.failures.0);
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct Product {
     pub title: String,
     pub image: String,
     pub url: String,
-    pub price: f64,
-    pub ean: Option<u64>,
+    /// Price the product is currently sold for
+    pub price: Money,
+    /// Struck through price shown next to a discounted price, e.g. the "van" price on bol
+    pub list_price: Option<Money>,
+    pub ean: Option<Gtin>,
+    /// Identifier of the product within the shop, e.g. the ASIN on amazon
+    pub sku: Option<String>,
+    pub brand: Option<String>,
+    pub description: Option<String>,
+    /// Highlighted product features, e.g. the bullet points on amazon
+    pub features: Vec<String>,
+    pub specifications: Specifications,
+    /// Shop the product was found in, when a shop serves multiple countries or languages
+    pub locale: Option<Locale>,
+    /// Paid placement in the search results rather than an organic result
+    pub sponsored: bool,
+    /// Average amount of stars out of 5
+    pub rating: Option<f64>,
+    pub review_count: Option<u32>,
+    pub availability: Option<Availability>,
+    pub delivery: Option<Delivery>,
+    /// Name of the shop selling the product, e.g. a partner on bol
+    pub seller: Option<String>,
+    /// Whether the product is sold by the platform itself rather than a third party
+    pub sold_by_platform: Option<bool>,
+    /// Breadcrumb path in the shop's category tree, from the top level category down to the leaf
+    pub categories: Vec<String>,
 }
 
-#[derive(Debug)]
+/// Language and country of a shop, e.g. `fr-BE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub language: &'static str,
+    pub country: &'static str,
+}
+
+impl Locale {
+    pub const fn new(language: &'static str, country: &'static str) -> Self {
+        Self { language, country }
+    }
+}
+
+impl Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.language, self.country)
+    }
+}
+
+impl Product {
+    /// Collapse whitespace in every text of the product,
+    /// optionally shortening the free text (title, description and features) to `max_chars`
+    pub fn normalize_text(&mut self, max_chars: Option<usize>) {
+        let normalize = |text: &mut String| *text = text::normalize(text);
+        let shorten = |text: &mut String| {
+            normalize(text);
+            if let Some(max_chars) = max_chars {
+                *text = text::truncate(text, max_chars);
+            }
+        };
+
+        shorten(&mut self.title);
+        self.sku.iter_mut().for_each(normalize);
+        self.brand.iter_mut().for_each(normalize);
+        self.seller.iter_mut().for_each(normalize);
+        if let Some(delivery) = &mut self.delivery {
+            normalize(&mut delivery.text);
+        }
+        self.description.iter_mut().for_each(shorten);
+        self.features.iter_mut().for_each(shorten);
+        self.features.retain(|feature| !feature.is_empty());
+        self.categories.iter_mut().for_each(normalize);
+        self.categories.retain(|category| !category.is_empty());
+        for spec in self.specifications.0.iter_mut() {
+            normalize(&mut spec.section);
+            normalize(&mut spec.name);
+            normalize(&mut spec.value);
+        }
+    }
+
+    /// Categories joined into a single path, e.g. "Mooi & Gezond > Mondverzorging"
+    pub fn category_path(&self) -> String {
+        self.categories.join(" > ")
+    }
+
+    /// Discount compared to the list price as a percentage, e.g. `25.00`
+    pub fn discount(&self) -> Option<Decimal> {
+        let list_price = self.list_price?;
+        if list_price.currency != self.price.currency || list_price.amount <= self.price.amount {
+            return None;
+        }
+
+        let discount = (list_price.amount - self.price.amount) / list_price.amount;
+        Some((discount * Decimal::ONE_HUNDRED).round_dp(2))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Specification {
+    /// Heading of the group this specification is listed under, e.g. "Afmetingen en gewicht"
+    pub section: String,
+    pub name: String,
+    pub value: String,
+}
+
+/// Product specifications in the order the shop lists them
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Specifications(Vec<Specification>);
+
+impl Specifications {
+    pub fn insert(&mut self, section: &str, name: impl Into<String>, value: impl Into<String>) {
+        self.0.push(Specification {
+            section: section.to_string(),
+            name: name.into(),
+            value: value.into(),
+        });
+    }
+
+    /// Value of the first specification with the given name, regardless of section
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|spec| spec.name == name)
+            .map(|spec| spec.value.as_str())
+    }
+}
+
+impl Deref for Specifications {
+    type Target = Vec<Specification>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[derive(Debug, Default)]
 pub struct Products(Vec<Product>);
 
 lazy_static! {
-    static ref page_param_regex: Regex = Regex::new(r"page=\d*").unwrap();
+    static ref page_param_regex: Regex = Regex::new(r"([?&])page=(\d*)").unwrap();
 }
 
+/// Page number in the `page` query parameter of the url
+pub fn page_from_url(url: &str) -> Option<usize> {
+    page_param_regex.captures(url)?[2].parse().ok()
+}
+
+/// Set the `page` query parameter of the url, replacing it when the url already has one
 pub fn paginate_url(url: &str, page: usize) -> String {
     if !url.contains("?") {
         return format!("{}?page={}", url, page);
@@ -90,29 +771,142 @@ pub fn paginate_url(url: &str, page: usize) -> String {
         format!("{}&page={}", url, page)
     } else {
         page_param_regex
-            .replace(url, format!("page={}", page).as_str())
+            .replace(url, format!("${{1}}page={}", page).as_str())
             .to_string()
     }
 }
 
+/// Write the amount as a number, formatted in its currency
+fn write_money(
+    worksheet: &mut rust_xlsxwriter::Worksheet,
+    row: u32,
+    col: u16,
+    money: &Money,
+) -> Result<(), rust_xlsxwriter::XlsxError> {
+    let format = rust_xlsxwriter::Format::new().set_num_format(money.currency.excel_format());
+    worksheet.write_number_with_format(row, col, money.to_f64(), &format)?;
+    Ok(())
+}
+
 impl Products {
-    pub fn as_worksheet(&self) -> Result<rust_xlsxwriter::Worksheet> {
+    pub fn as_worksheet(&self) -> Result<rust_xlsxwriter::Worksheet, rust_xlsxwriter::XlsxError> {
         let mut worksheet = rust_xlsxwriter::Worksheet::new();
 
-        let column_names = ["title", "image", "url", "price", "ean"];
+        let column_names = [
+            "title",
+            "image",
+            "url",
+            "price",
+            "list price",
+            "discount %",
+            "ean",
+            "sku",
+            "brand",
+            "description",
+            "features",
+            "locale",
+            "sponsored",
+            "rating",
+            "reviews",
+            "availability",
+            "delivery",
+            "delivery days",
+            "seller",
+            "sold by platform",
+            "category",
+            "leaf category",
+        ];
         for (col, name) in column_names.iter().enumerate() {
             worksheet.write(0, col as u16, *name)?;
         }
 
+        // delivery dates are counted from the day of the export
+        let today = chrono::Local::now().date_naive();
         for (i, product) in self.0.iter().enumerate() {
             let row = (i + 1) as u32;
             worksheet.write(row, 0, &product.title)?;
             worksheet.write(row, 1, &product.image)?;
             worksheet.write(row, 2, &product.url)?;
-            worksheet.write(row, 3, product.price)?;
-            if let Some(ean) = product.ean {
-                worksheet.write(row, 4, ean)?;
+            write_money(&mut worksheet, row, 3, &product.price)?;
+            if let Some(list_price) = &product.list_price {
+                write_money(&mut worksheet, row, 4, list_price)?;
+            }
+            if let Some(discount) = product.discount().and_then(|d| d.to_f64()) {
+                worksheet.write(row, 5, discount)?;
+            }
+            // written as text, a number would drop the leading zeros
+            if let Some(ean) = &product.ean {
+                worksheet.write(row, 6, ean.as_str())?;
+            }
+            if let Some(sku) = &product.sku {
+                worksheet.write(row, 7, sku)?;
+            }
+            if let Some(brand) = &product.brand {
+                worksheet.write(row, 8, brand)?;
+            }
+            if let Some(description) = &product.description {
+                worksheet.write(row, 9, description)?;
+            }
+            worksheet.write(row, 10, product.features.join("\n"))?;
+            if let Some(locale) = product.locale {
+                worksheet.write(row, 11, locale.to_string())?;
+            }
+            worksheet.write(row, 12, product.sponsored)?;
+            if let Some(rating) = product.rating {
+                worksheet.write(row, 13, rating)?;
+            }
+            if let Some(review_count) = product.review_count {
+                worksheet.write(row, 14, review_count)?;
+            }
+            if let Some(availability) = product.availability {
+                worksheet.write(row, 15, availability.to_string())?;
+            }
+            if let Some(delivery) = &product.delivery {
+                worksheet.write(row, 16, &delivery.text)?;
+                if let Some(days) = delivery.days(today) {
+                    worksheet.write(row, 17, days)?;
+                }
+            }
+            if let Some(seller) = &product.seller {
+                worksheet.write(row, 18, seller)?;
+            }
+            if let Some(sold_by_platform) = product.sold_by_platform {
+                worksheet.write(row, 19, sold_by_platform)?;
             }
+            if let Some(leaf) = product.categories.last() {
+                worksheet.write(row, 20, product.category_path())?;
+                worksheet.write(row, 21, leaf)?;
+            }
+        }
+
+        Ok(worksheet)
+    }
+
+    /// Specifications of every product, one row per specification
+    pub fn specifications_worksheet(
+        &self,
+    ) -> Result<rust_xlsxwriter::Worksheet, rust_xlsxwriter::XlsxError> {
+        let mut worksheet = rust_xlsxwriter::Worksheet::new();
+        worksheet.set_name("specificaties")?;
+
+        let column_names = ["url", "section", "name", "value"];
+        for (col, name) in column_names.iter().enumerate() {
+            worksheet.write(0, col as u16, *name)?;
+        }
+
+        let specifications = self.0.iter().flat_map(|product| {
+            product
+                .specifications
+                .iter()
+                .map(move |spec| (product, spec))
+        });
+
+        for (i, (product, spec)) in specifications.enumerate() {
+            let row = (i + 1) as u32;
+            worksheet.write(row, 0, &product.url)?;
+            worksheet.write(row, 1, &spec.section)?;
+            worksheet.write(row, 2, &spec.name)?;
+            worksheet.write(row, 3, &spec.value)?;
         }
 
         Ok(worksheet)
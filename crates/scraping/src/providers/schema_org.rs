@@ -0,0 +1,86 @@
+use super::*;
+use crate::structured;
+
+/// Any webshop that describes its products with schema.org structured data.
+///
+/// Registered last, so it's only used for shops without a dedicated provider.
+pub struct SchemaOrg;
+
+/// Shops with a dedicated provider, their unsupported countries shouldn't fall back to this one
+const BUILTIN_SHOPS: &[&str] = &["amazon", "bol"];
+
+impl ShopProvider for SchemaOrg {
+    fn matches_url(&self, url: &str) -> bool {
+        if !(url.starts_with("https://") || url.starts_with("http://")) {
+            return false;
+        }
+
+        match domain_parts(url).as_deref() {
+            Some([shop, ..]) => !BUILTIN_SHOPS.contains(shop),
+            _ => false,
+        }
+    }
+
+    fn parse_listing(&self, state: &status::State, url: &str, doc: &Html) -> Result<Listing> {
+        if let Some(listing) = structured_listing(state, doc, url) {
+            return Ok(listing);
+        }
+
+        Ok(Listing {
+            products: product_links(url, doc),
+            failures: Vec::new(),
+        })
+    }
+
+    fn enriches_products(&self) -> bool {
+        true
+    }
+
+    fn enrich_product(&self, product: &mut Product, doc: &Html) -> Result<()> {
+        let data = structured::product(doc).or_layout("schema.org product")?;
+        data.fill(product);
+        if product.categories.is_empty() {
+            product.categories = structured::breadcrumbs(doc);
+        }
+
+        if product.title.is_empty() {
+            return Err(ScrapeError::parse("title", "product has no name"));
+        }
+        Ok(())
+    }
+}
+
+lazy_static! {
+    static ref link_selector: Selector = Selector::parse("a[href]").unwrap();
+    /// Product paths used by common shop systems, e.g. `/p/...` or `/products/...`
+    static ref product_path_regex: Regex =
+        Regex::new(r"(?i)/(p|product|products|produkt|artikel|item)/[^/?#]+").unwrap();
+}
+
+/// Links on the listing that look like product pages on the same shop,
+/// the rest of the product is filled in from its detail page
+fn product_links(url: &str, doc: &Html) -> Vec<Product> {
+    let host = domain_parts(url);
+    let mut products: Vec<Product> = Vec::new();
+
+    for link in doc.select(&link_selector) {
+        let Some(href) = link.attr("href").and_then(|href| absolute_url(url, href)) else {
+            continue;
+        };
+        if domain_parts(&href) != host || !product_path_regex.is_match(&href) {
+            continue;
+        }
+        // the same product is usually linked from both its image and title
+        let href = href.split('#').next().unwrap_or_default().to_string();
+        if products.iter().any(|product| product.url == href) {
+            continue;
+        }
+
+        products.push(Product {
+            url: href,
+            ..Default::default()
+        });
+    }
+
+    products
+}
@@ -0,0 +1,149 @@
+//! Retrying of failed fetches with exponential backoff
+
+use crate::{
+    error::Result,
+    fetch::{Fetcher, Response},
+    status,
+};
+use futures::future::BoxFuture;
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total amount of attempts, including the first one
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every following retry
+    pub base_delay: Duration,
+    /// Upper bound for the delay between attempts, also applies to `Retry-After`
+    pub max_delay: Duration,
+    /// Fraction of the delay that is randomized, between 0 and 1
+    pub jitter: f64,
+    /// Response statuses worth retrying, failed requests are retried when the error is transient
+    pub retryable_statuses: Vec<StatusCode>,
+    /// Wait as long as the server asks for with the `Retry-After` header
+    pub honor_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+            retryable_statuses: vec![
+                StatusCode::REQUEST_TIMEOUT,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                StatusCode::BAD_GATEWAY,
+                StatusCode::SERVICE_UNAVAILABLE,
+                StatusCode::GATEWAY_TIMEOUT,
+            ],
+            honor_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that only makes a single attempt
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_retryable(&self, status: StatusCode) -> bool {
+        self.retryable_statuses.contains(&status)
+    }
+
+    /// Delay before the given retry, retries start at 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exponent = retry.saturating_sub(1).min(16);
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_delay);
+
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return delay;
+        }
+
+        // spread out the delay to avoid all requests retrying at the same moment
+        let factor = rand::thread_rng().gen_range(1.0 - jitter..=1.0);
+        delay.mul_f64(factor)
+    }
+
+    /// Delay requested by the server through the `Retry-After` header
+    fn retry_after(&self, res: &Response) -> Option<Duration> {
+        if !self.honor_retry_after {
+            return None;
+        }
+
+        let value = res.headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+        let delay = match value.parse::<u64>() {
+            Ok(seconds) => Duration::from_secs(seconds),
+            Err(_) => httpdate::parse_http_date(value)
+                .ok()?
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        };
+
+        Some(delay.min(self.max_delay))
+    }
+}
+
+/// Wraps a fetcher and retries failed requests according to a [`RetryPolicy`]
+pub struct RetryFetcher<F> {
+    inner: F,
+    policy: RetryPolicy,
+    state: status::State,
+}
+
+impl<F: Fetcher> RetryFetcher<F> {
+    /// Retries are reported to `state`
+    pub fn new(inner: F, policy: RetryPolicy, state: status::State) -> Self {
+        Self {
+            inner,
+            policy,
+            state,
+        }
+    }
+}
+
+impl<F: Fetcher> Fetcher for RetryFetcher<F> {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let mut attempt = 1;
+            loop {
+                let res = self.inner.fetch(url).await;
+                let delay = match &res {
+                    _ if attempt >= self.policy.max_attempts => return res,
+                    Ok(ok) if !self.policy.is_retryable(ok.status) => return res,
+                    Ok(res) => self
+                        .policy
+                        .retry_after(res)
+                        .unwrap_or_else(|| self.policy.backoff(attempt)),
+                    Err(err) if !err.is_transient() => return res,
+                    Err(_) => self.policy.backoff(attempt),
+                };
+
+                match res {
+                    Ok(res) => {
+                        eprintln!("Got status {} for {url}, retrying in {delay:?}", res.status)
+                    }
+                    Err(err) => {
+                        eprintln!("Error while fetching {url}: {err:#}, retrying in {delay:?}")
+                    }
+                }
+
+                self.state.add_retry();
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        })
+    }
+}
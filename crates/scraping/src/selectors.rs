@@ -0,0 +1,154 @@
+//! Css selectors used by the providers.
+//!
+//! Shops change their pages more often than we release, so the selectors are read from
+//! a definitions file instead of being compiled into the providers.
+//! The defaults in `selectors.toml` are embedded, a local file or downloaded bundle
+//! can override any of them with [`Definitions::merge`] before they are [`install`]ed.
+
+use crate::error::DefinitionsError;
+use scraper::Selector;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+};
+
+static DEFAULTS: &str = include_str!("../selectors.toml");
+
+static ACTIVE: OnceLock<Definitions> = OnceLock::new();
+
+/// Compiled selectors for every shop, grouped by shop
+#[derive(Debug, Clone)]
+pub struct Definitions {
+    shops: BTreeMap<String, BTreeMap<String, Definition>>,
+}
+
+/// A single selector together with its source, the source is kept for reporting
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub source: String,
+    pub selector: Selector,
+}
+
+impl Definitions {
+    /// The selectors compiled into the program
+    pub fn defaults() -> Self {
+        let mut definitions = Self {
+            shops: BTreeMap::new(),
+        };
+        for (shop, selectors) in parse(DEFAULTS).expect("embedded selectors to be valid") {
+            let selectors = selectors
+                .into_iter()
+                .map(|(name, source)| {
+                    let definition = compile(&shop, &name, source);
+                    (name, definition.expect("embedded selectors to be valid"))
+                })
+                .collect();
+            definitions.shops.insert(shop, selectors);
+        }
+
+        definitions
+    }
+
+    /// Override selectors with the ones defined in `toml`.
+    /// Only known selectors can be overridden and all of them have to be valid,
+    /// nothing is changed when any of them isn't.
+    pub fn merge(&mut self, toml: &str) -> Result<(), DefinitionsError> {
+        self.merge_with(toml, true).map(|_| ())
+    }
+
+    /// Like [`merge`](Self::merge), but unknown shops and selectors are skipped instead of rejected.
+    /// Meant for the published definitions, which may already contain selectors of a newer release.
+    /// Returns the skipped selectors as `shop.name`, or just the shop when the whole shop is unknown.
+    pub fn merge_known(&mut self, toml: &str) -> Result<Vec<String>, DefinitionsError> {
+        self.merge_with(toml, false)
+    }
+
+    fn merge_with(&mut self, toml: &str, strict: bool) -> Result<Vec<String>, DefinitionsError> {
+        let mut merged = self.shops.clone();
+        let mut skipped = Vec::new();
+
+        for (shop, selectors) in parse(toml)? {
+            let Some(known) = merged.get_mut(&shop) else {
+                if strict {
+                    return Err(DefinitionsError::UnknownShop { shop });
+                }
+                skipped.push(shop);
+                continue;
+            };
+
+            for (name, source) in selectors {
+                let Some(definition) = known.get_mut(&name) else {
+                    if strict {
+                        return Err(DefinitionsError::UnknownSelector { shop, name });
+                    }
+                    skipped.push(format!("{shop}.{name}"));
+                    continue;
+                };
+                *definition = compile(&shop, &name, source)?;
+            }
+        }
+
+        self.shops = merged;
+        Ok(skipped)
+    }
+
+    /// Selector called `name` of the given shop
+    ///
+    /// # Panics
+    /// When the selector isn't defined, every selector a provider uses has a default
+    pub fn get(&self, shop: &str, name: &str) -> &Selector {
+        match self.definition(shop, name) {
+            Some(definition) => &definition.selector,
+            None => panic!("selector '{name}' of {shop} is not defined"),
+        }
+    }
+
+    pub fn definition(&self, shop: &str, name: &str) -> Option<&Definition> {
+        self.shops.get(shop)?.get(name)
+    }
+
+    /// Every selector of a shop by name
+    pub fn shop(&self, shop: &str) -> impl Iterator<Item = (&str, &Definition)> {
+        self.shops
+            .get(shop)
+            .into_iter()
+            .flatten()
+            .map(|(name, definition)| (name.as_str(), definition))
+    }
+}
+
+impl Default for Definitions {
+    fn default() -> Self {
+        Self::defaults()
+    }
+}
+
+fn parse(toml: &str) -> Result<HashMap<String, BTreeMap<String, String>>, DefinitionsError> {
+    toml::from_str(toml).map_err(|err| DefinitionsError::Toml(Box::new(err)))
+}
+
+fn compile(shop: &str, name: &str, source: String) -> Result<Definition, DefinitionsError> {
+    let selector = Selector::parse(&source).map_err(|err| DefinitionsError::InvalidSelector {
+        shop: shop.to_string(),
+        name: name.to_string(),
+        reason: err.to_string(),
+    })?;
+
+    Ok(Definition { source, selector })
+}
+
+/// Use these definitions for the rest of the program,
+/// fails when selectors were already used or installed before
+pub fn install(definitions: Definitions) -> Result<(), Definitions> {
+    ACTIVE.set(definitions)
+}
+
+/// The installed definitions, or the defaults when none were installed
+pub fn active() -> &'static Definitions {
+    ACTIVE.get_or_init(Definitions::defaults)
+}
+
+/// Shorthand for looking up a selector in the [`active`] definitions
+pub fn get(shop: &str, name: &str) -> &'static Selector {
+    active().get(shop, name)
+}
@@ -29,6 +29,7 @@ pub struct QueryStatus {
     pub errored: AtomicU32,
 
     pub duplicates: AtomicU32,
+    pub retries: AtomicU32,
 }
 
 impl Display for QueryStatus {
@@ -38,7 +39,8 @@ impl Display for QueryStatus {
         writeln!(f, "done: {}", self.success.load())?;
         writeln!(f, "errored: {}", self.errored.load())?;
 
-        writeln!(f, "duplicates: {}", self.errored.load())
+        writeln!(f, "duplicates: {}", self.errored.load())?;
+        writeln!(f, "retries: {}", self.retries.load())
     }
 }
 
@@ -51,7 +53,6 @@ pub struct Status {
     on_update: Option<Arc<OnUpdate>>,
 }
 
-
 impl Status {
     /// Takes in a callback function that will be called on status change.
     /// This function may be called across multiple threads
@@ -65,6 +66,10 @@ impl Status {
         })
     }
 
+    pub fn queries(&self) -> &QueryStatus {
+        &self.queries
+    }
+
     fn update(&self) {
         if let Some(callback) = &self.on_update {
             callback(&self.queries);
@@ -97,4 +102,10 @@ impl Status {
         self.queries.duplicates.increment();
         self.update();
     }
+
+    /// Increment retries count
+    pub fn add_retry(&self) {
+        self.queries.retries.increment();
+        self.update();
+    }
 }
@@ -0,0 +1,362 @@
+//! Extraction of schema.org product data embedded in pages, either as JSON-LD or as microdata.
+//!
+//! Shops add this data for search engines, so it tends to outlive changes to the visible page.
+
+use crate::{
+    money::{Currency, Money},
+    offer::Availability,
+    providers::{collapsed_text, ElementRef, Html, Product, Selector},
+    text::html_to_text,
+};
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+
+/// Product as described by schema.org structured data
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StructuredProduct {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    /// Any of `gtin13`, `gtin`, `gtin12`, `gtin14` or `gtin8`
+    pub gtin: Option<String>,
+    pub sku: Option<String>,
+    pub brand: Option<String>,
+    pub description: Option<String>,
+    pub price: Option<Money>,
+    /// Availability without the schema.org prefix, e.g. `InStock`
+    pub availability: Option<String>,
+    /// Name of the organization selling the product
+    pub seller: Option<String>,
+    pub rating: Option<f64>,
+    pub review_count: Option<u32>,
+    pub images: Vec<String>,
+}
+
+impl StructuredProduct {
+    /// Fill in the fields of `product` that are still empty
+    pub fn fill(&self, product: &mut Product) {
+        fn fill_string(field: &mut String, value: &Option<String>) {
+            if let (true, Some(value)) = (field.is_empty(), value) {
+                field.clone_from(value);
+            }
+        }
+
+        fill_string(&mut product.title, &self.name);
+        fill_string(&mut product.url, &self.url);
+        if let (true, Some(image)) = (product.image.is_empty(), self.images.first()) {
+            product.image.clone_from(image);
+        }
+        if let (true, Some(price)) = (product.price.is_zero(), self.price) {
+            product.price = price;
+        }
+
+        if product.ean.is_none() {
+            product.ean = self.gtin.as_ref().and_then(|gtin| gtin.parse().ok());
+        }
+        if product.sku.is_none() {
+            product.sku.clone_from(&self.sku);
+        }
+        if product.brand.is_none() {
+            product.brand.clone_from(&self.brand);
+        }
+        if product.description.is_none() {
+            product.description.clone_from(&self.description);
+        }
+        if product.rating.is_none() {
+            product.rating = self.rating;
+        }
+        if product.review_count.is_none() {
+            product.review_count = self.review_count;
+        }
+        if product.availability.is_none() {
+            product.availability = self
+                .availability
+                .as_deref()
+                .and_then(Availability::from_schema_org);
+        }
+        if product.seller.is_none() {
+            product.seller.clone_from(&self.seller);
+        }
+    }
+
+    /// Create a product from structured data alone, requires at least a name and url
+    pub fn to_product(&self) -> Option<Product> {
+        if self.name.is_none() || self.url.is_none() {
+            return None;
+        }
+
+        let mut product = Product::default();
+        self.fill(&mut product);
+        Some(product)
+    }
+
+    fn from_value(item: &Map<String, Value>) -> Self {
+        let offer = item
+            .get("offers")
+            .and_then(first)
+            .and_then(Value::as_object);
+        let rating = item
+            .get("aggregateRating")
+            .and_then(first)
+            .and_then(Value::as_object);
+
+        Self {
+            name: text(item.get("name")),
+            url: text(item.get("url")),
+            gtin: ["gtin13", "gtin", "gtin12", "gtin14", "gtin8"]
+                .iter()
+                .find_map(|key| text(item.get(*key))),
+            sku: text(item.get("sku")),
+            brand: item
+                .get("brand")
+                .and_then(first)
+                .and_then(|brand| match brand {
+                    Value::Object(brand) => text(brand.get("name")),
+                    brand => text(Some(brand)),
+                }),
+            // shops often put the HTML of their description in here as is
+            description: text(item.get("description")).map(|html| html_to_text(&html)),
+            price: offer.and_then(|offer| {
+                let currency = text(offer.get("priceCurrency"))
+                    .and_then(|currency| Currency::parse(&currency))
+                    .unwrap_or_default();
+                let price = text(offer.get("price")).or_else(|| text(offer.get("lowPrice")))?;
+                Money::parse(&price, currency).ok()
+            }),
+            availability: offer.and_then(|offer| text(offer.get("availability"))).map(
+                |availability| match availability.rsplit_once('/') {
+                    Some((_, availability)) => availability.to_string(),
+                    None => availability,
+                },
+            ),
+            seller: offer
+                .and_then(|offer| offer.get("seller"))
+                .and_then(first)
+                .and_then(|seller| match seller {
+                    Value::Object(seller) => text(seller.get("name")),
+                    seller => text(Some(seller)),
+                }),
+            rating: rating.and_then(|rating| number(rating.get("ratingValue"))),
+            review_count: rating.and_then(|rating| {
+                number(rating.get("reviewCount"))
+                    .or_else(|| number(rating.get("ratingCount")))
+                    .map(|count| count as u32)
+            }),
+            images: item
+                .get("image")
+                .map(|image| match image {
+                    Value::Array(images) => images.iter().filter_map(image_url).collect(),
+                    image => image_url(image).into_iter().collect(),
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+lazy_static! {
+    static ref json_ld_selector: Selector =
+        Selector::parse(r#"script[type="application/ld+json"]"#).unwrap();
+    static ref microdata_selector: Selector =
+        Selector::parse(r#"[itemscope][itemtype*="schema.org/Product"]"#).unwrap();
+}
+
+/// Every product described on the page, JSON-LD before microdata
+pub fn products(doc: &Html) -> Vec<StructuredProduct> {
+    let mut items = Vec::new();
+
+    for script in doc.select(&json_ld_selector) {
+        let json = script.text().collect::<String>();
+        // broken JSON-LD is not uncommon, skip it like search engines do
+        if let Ok(value) = serde_json::from_str::<Value>(&json) {
+            collect_json_ld(&value, &mut items);
+        }
+    }
+
+    for el in doc.select(&microdata_selector) {
+        // nested products, e.g. related products, are found by the selector on their own
+        if let Value::Object(item) = microdata_item(el) {
+            items.push(item);
+        }
+    }
+
+    items.iter().map(StructuredProduct::from_value).collect()
+}
+
+/// The first product described on the page, for product pages this is the product itself
+pub fn product(doc: &Html) -> Option<StructuredProduct> {
+    products(doc).into_iter().next()
+}
+
+/// Category path of the first breadcrumb trail on the page, from the top level down
+pub fn breadcrumbs(doc: &Html) -> Vec<String> {
+    for script in doc.select(&json_ld_selector) {
+        let json = script.text().collect::<String>();
+        let Ok(value) = serde_json::from_str::<Value>(&json) else {
+            continue;
+        };
+        if let Some(list) = find_breadcrumb_list(&value) {
+            return breadcrumb_names(list);
+        }
+    }
+
+    Vec::new()
+}
+
+fn find_breadcrumb_list(value: &Value) -> Option<&Map<String, Value>> {
+    match value {
+        Value::Array(values) => values.iter().find_map(find_breadcrumb_list),
+        Value::Object(item) if is_type(item, "BreadcrumbList") => Some(item),
+        Value::Object(item) => item.get("@graph").and_then(find_breadcrumb_list),
+        _ => None,
+    }
+}
+
+fn breadcrumb_names(list: &Map<String, Value>) -> Vec<String> {
+    let Some(Value::Array(elements)) = list.get("itemListElement") else {
+        return Vec::new();
+    };
+
+    let mut crumbs = elements
+        .iter()
+        .filter_map(Value::as_object)
+        .filter_map(|element| {
+            // the name is either on the list item or on the page it links to
+            let name = text(element.get("name")).or_else(|| match element.get("item") {
+                Some(Value::Object(item)) => text(item.get("name")),
+                _ => None,
+            })?;
+            let position = number(element.get("position")).unwrap_or(f64::MAX);
+            Some((position, name))
+        })
+        .collect::<Vec<_>>();
+    crumbs.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    crumbs.into_iter().map(|(_, name)| name).collect()
+}
+
+fn is_type(item: &Map<String, Value>, kind: &str) -> bool {
+    match item.get("@type") {
+        Some(Value::String(item_kind)) => item_kind == kind,
+        Some(Value::Array(kinds)) => kinds.iter().any(|item_kind| item_kind == kind),
+        _ => false,
+    }
+}
+
+fn is_product(item: &Map<String, Value>) -> bool {
+    is_type(item, "Product")
+}
+
+/// Find products in a JSON-LD document, including those nested in a `@graph` or `ItemList`
+fn collect_json_ld(value: &Value, items: &mut Vec<Map<String, Value>>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_json_ld(value, items);
+            }
+        }
+        Value::Object(item) if is_product(item) => items.push(item.clone()),
+        Value::Object(item) => {
+            for key in ["@graph", "itemListElement", "item", "mainEntity"] {
+                if let Some(value) = item.get(key) {
+                    collect_json_ld(value, items);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Convert a microdata item into the same shape as JSON-LD
+fn microdata_item(el: ElementRef<'_>) -> Value {
+    let mut item = Map::new();
+    if let Some(kind) = el.attr("itemtype") {
+        let kind = kind.rsplit('/').next().unwrap_or(kind);
+        item.insert("@type".into(), Value::String(kind.to_string()));
+    }
+
+    collect_microdata(el, &mut item);
+    Value::Object(item)
+}
+
+fn collect_microdata(el: ElementRef<'_>, item: &mut Map<String, Value>) {
+    for child in el.child_elements() {
+        let is_scope = child.attr("itemscope").is_some();
+
+        let Some(names) = child.attr("itemprop") else {
+            // a new item that isn't a property of this one
+            if !is_scope {
+                collect_microdata(child, item);
+            }
+            continue;
+        };
+
+        let value = match is_scope {
+            true => microdata_item(child),
+            false => Value::String(microdata_value(child)),
+        };
+
+        for name in names.split_whitespace() {
+            // repeated properties become a list, like they would in JSON-LD
+            match item.get_mut(name) {
+                Some(Value::Array(values)) => values.push(value.clone()),
+                Some(existing) => *existing = Value::Array(vec![existing.clone(), value.clone()]),
+                None => {
+                    item.insert(name.to_string(), value.clone());
+                }
+            }
+        }
+
+        if !is_scope {
+            collect_microdata(child, item);
+        }
+    }
+}
+
+fn microdata_value(el: ElementRef<'_>) -> String {
+    let attr = match el.value().name() {
+        "meta" => "content",
+        "a" | "link" | "area" => "href",
+        "img" | "source" | "video" | "audio" | "iframe" | "embed" => "src",
+        "data" | "meter" => "value",
+        "time" => "datetime",
+        _ => {
+            return el
+                .attr("content")
+                .map(str::to_string)
+                .unwrap_or_else(|| collapsed_text(el))
+        }
+    };
+
+    el.attr(attr).unwrap_or_default().trim().to_string()
+}
+
+/// First value when multiple are given
+fn first(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Array(values) => values.first(),
+        value => Some(value),
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    let text = match first(value?)? {
+        Value::String(text) => text.trim().to_string(),
+        Value::Number(number) => number.to_string(),
+        _ => return None,
+    };
+
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn number(value: Option<&Value>) -> Option<f64> {
+    match first(value?)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().replace(',', ".").parse().ok(),
+        _ => None,
+    }
+}
+
+fn image_url(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(image) => text(image.get("url").or(image.get("contentUrl"))),
+        value => text(Some(value)),
+    }
+}
@@ -0,0 +1,58 @@
+//! Cleaning up text taken from a page before it ends up in the sheet.
+
+use scraper::Html;
+use unicode_normalization::UnicodeNormalization;
+
+/// Invisible text direction marks amazon puts around specification names
+const DIRECTION_MARKS: [char; 2] = ['\u{200e}', '\u{200f}'];
+
+/// Collapse whitespace in already plain text into single spaces
+/// and normalize the result to Unicode NFC
+pub fn normalize(text: &str) -> String {
+    collapse([text])
+}
+
+/// Plain text from a snippet of raw HTML, like the description in JSON-LD:
+/// entities are decoded and tags stripped before the text is normalized
+pub fn html_to_text(html: &str) -> String {
+    let fragment = Html::parse_fragment(html);
+    collapse(fragment.root_element().text())
+}
+
+/// Join text nodes, collapsing whitespace and dropping text direction marks
+pub(crate) fn collapse<'a>(parts: impl IntoIterator<Item = &'a str>) -> String {
+    parts
+        .into_iter()
+        .flat_map(str::split_whitespace)
+        .map(|word| word.trim_matches(DIRECTION_MARKS))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .nfc()
+        .collect()
+}
+
+/// Shorten the text to at most `max_chars` characters, cutting at the last whole word
+/// and marking the cut with an ellipsis
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    // not even the ellipsis fits
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    // leave room for the ellipsis
+    let end = text
+        .char_indices()
+        .nth(max_chars.saturating_sub(1))
+        .map_or(text.len(), |(i, _)| i);
+    let cut = &text[..end];
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > 0 && !text[end..].starts_with(' ') => &cut[..space],
+        _ => cut,
+    };
+
+    format!("{}…", cut.trim_end())
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::*;
+use scraping::{
+    check::{self, Check},
+    fetch::{MockFetcher, Response},
+};
+
+fn find(shop: &str) -> Check {
+    check::defaults()
+        .into_iter()
+        .find(|check| check.shop == shop)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn healthy_shops() {
+    for (shop, listing, product) in [
+        ("bol", "bol/listing.html", "bol/product.html"),
+        ("amazon", "amazon/listing.html", "amazon/product.html"),
+    ] {
+        let check = find(shop);
+        let fetcher = MockFetcher::new()
+            .respond(&check.listing_url, Response::ok(fixture(listing)))
+            .respond(&check.product_url, Response::ok(fixture(product)));
+
+        let report = check.run(&fetcher, &state()).await;
+        assert!(report.is_healthy(), "{report}");
+    }
+}
+
+#[tokio::test]
+async fn reports_layout_changes() {
+    let check = find("bol");
+    let fetcher = MockFetcher::new()
+        .respond(
+            &check.listing_url,
+            Response::ok(fixture("amazon/listing.html")),
+        )
+        .respond(
+            &check.product_url,
+            Response::ok(fixture("bol/product.html")),
+        );
+
+    let report = check.run(&fetcher, &state()).await;
+    assert!(!report.is_healthy());
+
+    let [listing, product] = &report.pages[..] else {
+        panic!("expected a listing and product page");
+    };
+    assert!(listing.error.is_some());
+    let container = listing
+        .selectors
+        .iter()
+        .find(|selector| selector.name == "container")
+        .unwrap();
+    assert_eq!(container.matches, 0);
+    assert!(product.is_healthy());
+    assert!(report.to_string().contains("- container"));
+}
+
+#[tokio::test]
+async fn reports_unreachable_pages() {
+    let check = find("amazon");
+    let report = check.run(&MockFetcher::new(), &state()).await;
+
+    assert!(!report.is_healthy());
+    assert!(report.pages.iter().all(|page| page.error.is_some()));
+}
@@ -0,0 +1,51 @@
+//! Helpers shared by the integration tests.
+//!
+//! Fixtures are saved copies of real shop pages, stored under `tests/fixtures/<provider>/`.
+//! Parser output is compared against golden snapshots in `tests/snapshots/`,
+//! run the tests with `UPDATE_SNAPSHOTS=1` to (re)generate them after an intentional change.
+#![allow(dead_code)]
+
+use scraping::{providers::Html, status::Status};
+use std::{fmt::Debug, path::PathBuf, sync::Arc};
+
+/// Listing urls the fixtures were saved from
+pub const BOL_URL: &str = "https://www.bol.com/nl/nl/l/elektrische-tandenborstels/12477/";
+pub const AMAZON_URL: &str = "https://www.amazon.nl/s?k=elektrische+tandenborstel";
+pub const SHOP_URL: &str = "https://www.fietsenwinkel.example/categorie/fietshelmen/";
+
+fn tests_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests")
+}
+
+pub fn fixture(path: &str) -> String {
+    let path = tests_dir().join("fixtures").join(path);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("unable to read fixture {}: {err}", path.display()))
+}
+
+pub fn fixture_dom(path: &str) -> Html {
+    Html::parse_document(&fixture(path))
+}
+
+pub fn state() -> Arc<Status> {
+    Arc::new(Status::default())
+}
+
+/// Compare the pretty printed debug output of `value` against the snapshot called `name`
+pub fn assert_snapshot(name: &str, value: &impl Debug) {
+    let path = tests_dir().join("snapshots").join(format!("{name}.snap"));
+    let actual = format!("{value:#?}\n");
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, &actual).expect("snapshot to be writable");
+        return;
+    }
+
+    let Ok(expected) = std::fs::read_to_string(&path) else {
+        panic!("snapshot '{name}' is missing, run with UPDATE_SNAPSHOTS=1 to create it\n\nactual:\n{actual}");
+    };
+    assert!(
+        expected == actual,
+        "snapshot '{name}' does not match, rerun with UPDATE_SNAPSHOTS=1 if this change is intended\n\nexpected:\n{expected}\nactual:\n{actual}"
+    );
+}
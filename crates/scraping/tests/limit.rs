@@ -0,0 +1,132 @@
+use futures::future::BoxFuture;
+use scraping::{
+    error::Result,
+    fetch::{Fetcher, Response},
+    limit::{LimitConfig, LimitedFetcher, Limiter},
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Fetcher that takes a while to respond and keeps track of how many requests run at once
+#[derive(Default)]
+struct SlowFetcher {
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl Fetcher for SlowFetcher {
+    fn fetch<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(Response::ok(""))
+        })
+    }
+}
+
+#[tokio::test]
+async fn limits_concurrent_requests() {
+    let limiter = Arc::new(Limiter::new(LimitConfig {
+        max_concurrent: 3,
+        requests_per_second: None,
+        delay: Duration::ZERO,
+    }));
+    let fetcher = LimitedFetcher::new(SlowFetcher::default(), limiter);
+
+    let requests = (0..12).map(|i| format!("https://www.bol.com/nl/nl/p/{i}/"));
+    let requests = requests.collect::<Vec<_>>();
+    futures::future::join_all(requests.iter().map(|url| fetcher.fetch(url))).await;
+
+    let fetcher = fetcher.into_inner();
+    assert_eq!(fetcher.max_in_flight.load(Ordering::SeqCst), 3);
+}
+
+// the clock is paused and only advances while every task waits, so the timing is exact
+#[tokio::test(start_paused = true)]
+async fn limits_requests_per_host() {
+    let limiter = Limiter::new(LimitConfig {
+        max_concurrent: 10,
+        requests_per_second: Some(20.0),
+        delay: Duration::ZERO,
+    });
+
+    let start = Instant::now();
+    for _ in 0..3 {
+        drop(limiter.acquire("https://www.bol.com/nl/nl/p/1/").await);
+    }
+    // a different host is not held back by the previous requests
+    drop(limiter.acquire("https://www.amazon.nl/s?k=test").await);
+
+    let elapsed = start.elapsed();
+    assert_eq!(elapsed, Duration::from_millis(100));
+}
+
+/// Fetcher that remembers when each request started, requests to bol take 10 seconds
+#[derive(Default)]
+struct RecordingFetcher {
+    started: Mutex<Vec<(String, Instant)>>,
+}
+
+impl Fetcher for RecordingFetcher {
+    fn fetch<'a>(&'a self, url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move {
+            let start = Instant::now();
+            self.started.lock().unwrap().push((url.to_string(), start));
+            if url.contains("bol.com") {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Ok(Response::ok(""))
+        })
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn waiting_for_a_permit_does_not_bunch_requests_to_a_host() {
+    let limiter = Arc::new(Limiter::new(LimitConfig {
+        max_concurrent: 2,
+        requests_per_second: Some(1.0),
+        delay: Duration::ZERO,
+    }));
+    let fetcher = LimitedFetcher::new(RecordingFetcher::default(), limiter);
+
+    let start = Instant::now();
+    let requests = [
+        "https://www.bol.com/nl/nl/p/1/",
+        "https://www.bol.com/be/fr/p/2/",
+        "https://www.amazon.nl/dp/1",
+        "https://www.amazon.nl/dp/2",
+    ];
+    futures::future::join_all(requests.iter().map(|url| fetcher.fetch(url))).await;
+
+    // both permits are held by bol for 10 seconds, after which amazon still gets one request per second
+    let started = fetcher.into_inner().started.into_inner().unwrap();
+    let amazon = started
+        .iter()
+        .filter(|(url, _)| url.contains("amazon"))
+        .map(|(_, time)| *time - start)
+        .collect::<Vec<_>>();
+    assert_eq!(amazon, [Duration::from_secs(10), Duration::from_secs(11)]);
+}
+
+#[tokio::test]
+async fn politeness_delay() {
+    let limiter = Limiter::new(LimitConfig {
+        max_concurrent: 10,
+        requests_per_second: None,
+        delay: Duration::from_millis(50),
+    });
+
+    let start = Instant::now();
+    drop(limiter.acquire("https://www.bol.com/").await);
+    drop(limiter.acquire("https://www.bol.com/").await);
+
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
@@ -0,0 +1,54 @@
+use scraping::money::{Currency, Decimal, Money};
+use std::str::FromStr;
+
+fn money(amount: &str, currency: Currency) -> Money {
+    Money::new(Decimal::from_str(amount).unwrap(), currency)
+}
+
+#[test]
+fn parse_prices() {
+    for (text, amount) in [
+        ("54.99", "54.99"),
+        ("54,99", "54.99"),
+        ("€ 1.299,00", "1299.00"),
+        ("1,299.00", "1299.00"),
+        ("1.299", "1299"),
+        ("1.234.567", "1234567"),
+        ("0,299", "0.299"),
+        ("€\u{a0}64,95", "64.95"),
+        ("64,95\u{202f}€", "64.95"),
+        ("54,-", "54"),
+        ("129", "129"),
+        ("EUR 12.5", "12.5"),
+    ] {
+        let parsed = text.parse::<Money>();
+        assert_eq!(parsed.ok(), Some(money(amount, Currency::Eur)), "{text}");
+    }
+}
+
+#[test]
+fn parse_currencies() {
+    assert_eq!(
+        Money::parse("£1,049.99", Currency::Eur).unwrap(),
+        money("1049.99", Currency::Gbp)
+    );
+    assert_eq!(
+        Money::parse("12.50", Currency::Usd).unwrap(),
+        money("12.50", Currency::Usd)
+    );
+    assert_eq!(Currency::parse("gbp"), Some(Currency::Gbp));
+    assert_eq!(Currency::parse("€"), Some(Currency::Eur));
+}
+
+#[test]
+fn parse_invalid_prices() {
+    for text in ["", "gratis", "€ -", ".99"] {
+        assert!(text.parse::<Money>().is_err(), "{text}");
+    }
+}
+
+#[test]
+fn display() {
+    assert_eq!(money("1299", Currency::Eur).to_string(), "€ 1299.00");
+    assert_eq!(money("12.5", Currency::Gbp).to_string(), "£ 12.50");
+}
@@ -0,0 +1,93 @@
+use chrono::NaiveDate;
+use scraping::offer::{Availability, Delivery, DeliveryEstimate};
+
+#[test]
+fn availability() {
+    for (text, availability) in [
+        ("Op voorraad", Some(Availability::InStock)),
+        (
+            "Voor 23:59 besteld, morgen in huis",
+            Some(Availability::InStock),
+        ),
+        (
+            "En stock. Commandé avant 23h59, livré demain",
+            Some(Availability::InStock),
+        ),
+        ("Tijdelijk uitverkocht", Some(Availability::OutOfStock)),
+        ("Currently unavailable.", Some(Availability::OutOfStock)),
+        ("Verwacht: 14 november", Some(Availability::PreOrder)),
+        ("Bezorging GRATIS op zo 20 okt", Some(Availability::InStock)),
+        ("Gratis retourneren", None),
+    ] {
+        assert_eq!(Availability::from_text(text), availability, "{text}");
+    }
+
+    assert_eq!(
+        Availability::from_schema_org("https://schema.org/OutOfStock"),
+        Some(Availability::OutOfStock)
+    );
+    assert_eq!(
+        Availability::from_schema_org("PreOrder"),
+        Some(Availability::PreOrder)
+    );
+    assert_eq!(Availability::from_schema_org("Unknown"), None);
+}
+
+#[test]
+fn delivery_estimate() {
+    for (text, estimate) in [
+        (
+            "Voor 23:59 besteld, morgen in huis",
+            Some(DeliveryEstimate::Days(1)),
+        ),
+        (
+            "Vandaag besteld, morgen in huis",
+            Some(DeliveryEstimate::Days(1)),
+        ),
+        (
+            "Commandé aujourd'hui, livré demain",
+            Some(DeliveryEstimate::Days(1)),
+        ),
+        ("Vandaag bezorgd", Some(DeliveryEstimate::Days(0))),
+        ("Overmorgen in huis", Some(DeliveryEstimate::Days(2))),
+        ("Uiterlijk 3 dagen in huis", Some(DeliveryEstimate::Days(3))),
+        (
+            "Usually dispatched within 2-4 days",
+            Some(DeliveryEstimate::Days(4)),
+        ),
+        (
+            "Bezorging GRATIS op zo 20 okt",
+            Some(DeliveryEstimate::Date { month: 10, day: 20 }),
+        ),
+        (
+            "Livraison GRATUITE mardi 22 octobre",
+            Some(DeliveryEstimate::Date { month: 10, day: 22 }),
+        ),
+        (
+            "FREE delivery Sunday, October 20",
+            Some(DeliveryEstimate::Date { month: 10, day: 20 }),
+        ),
+        (
+            "Livraison le 2 sept. 2024",
+            Some(DeliveryEstimate::Date { month: 9, day: 2 }),
+        ),
+        // words that only start like a month aren't dates
+        ("Verkocht via Marktplaats 5", None),
+        ("Levertijd 2 maanden", None),
+        ("Gratis retourneren", None),
+    ] {
+        assert_eq!(Delivery::parse(text).estimate, estimate, "{text}");
+    }
+}
+
+#[test]
+fn delivery_days() {
+    let today = NaiveDate::from_ymd_opt(2024, 10, 18).unwrap();
+    assert_eq!(Delivery::parse("zo 20 okt").days(today), Some(2));
+    assert_eq!(Delivery::parse("morgen in huis").days(today), Some(1));
+    assert_eq!(Delivery::parse("Gratis retourneren").days(today), None);
+
+    // a date earlier in the year is next year's
+    let today = NaiveDate::from_ymd_opt(2024, 12, 30).unwrap();
+    assert_eq!(Delivery::parse("do 2 jan").days(today), Some(3));
+}
@@ -0,0 +1,486 @@
+mod common;
+
+use common::*;
+use scraping::{
+    error::ScrapeError,
+    offer::DeliveryEstimate,
+    providers::{
+        amazon::{Amazon, Marketplace},
+        bol,
+        bol::Bol,
+        schema_org::SchemaOrg,
+        Availability, Currency, Gtin, Html, Product, ShopProvider,
+    },
+};
+
+#[test]
+fn bol_listing() {
+    let doc = fixture_dom("bol/listing.html");
+    let listing = Bol.parse_listing(&state(), BOL_URL, &doc).unwrap();
+
+    assert_eq!(listing.products.len(), 3);
+    // the sponsored banner at the end of the list is skipped, it's not a product
+    assert!(listing.failures.is_empty());
+    assert_snapshot("bol_listing", &listing);
+}
+
+#[test]
+fn bol_listing_discount() {
+    let doc = fixture_dom("bol/listing.html");
+    let listing = Bol.parse_listing(&state(), BOL_URL, &doc).unwrap();
+
+    // the from price is kept next to the actual price
+    let product = &listing.products[0];
+    assert_eq!(product.price.to_string(), "€ 54.99");
+    assert_eq!(
+        product.list_price.map(|p| p.to_string()).as_deref(),
+        Some("€ 69.99")
+    );
+    assert_eq!(
+        product.discount().map(|d| d.to_string()).as_deref(),
+        Some("21.43")
+    );
+
+    assert!(listing.products[1].list_price.is_none());
+    assert_eq!(listing.products[1].discount(), None);
+}
+
+#[test]
+fn bol_page_count() {
+    let doc = fixture_dom("bol/listing.html");
+    assert_eq!(Bol.page_count(&doc), Some(52));
+
+    let doc = fixture_dom("bol/listing_duplicates.html");
+    assert_eq!(Bol.page_count(&doc), None);
+}
+
+#[test]
+fn bol_listing_duplicates() {
+    let doc = fixture_dom("bol/listing_duplicates.html");
+    let state = state();
+    let listing = Bol.parse_listing(&state, BOL_URL, &doc).unwrap();
+
+    assert_eq!(listing.products.len(), 1);
+    assert!(listing.failures.is_empty());
+    assert_eq!(state.queries().duplicates.load(), 1);
+}
+
+#[test]
+fn bol_listing_unexpected_layout() {
+    let doc = fixture_dom("bol/product.html");
+    let res = Bol.parse_listing(&state(), BOL_URL, &doc);
+
+    assert!(matches!(res, Err(ScrapeError::LayoutChanged { .. })));
+}
+
+#[test]
+fn bol_listing_renamed_title() {
+    // rows that still link to a product aren't skipped, so the rename doesn't look like an empty page
+    let html = fixture("bol/listing.html")
+        .replace(r#"class="product-title px"#, r#"class="product-name px"#);
+    let res = Bol.parse_listing(&state(), BOL_URL, &Html::parse_document(&html));
+
+    assert!(matches!(res, Err(ScrapeError::LayoutChanged { selector }) if selector == "title"));
+}
+
+#[test]
+fn bol_product_page() {
+    let doc = fixture_dom("bol/product.html");
+    let specifications = bol::parse_product_page(&doc).unwrap();
+
+    assert_eq!(specifications.len(), 8);
+    assert_eq!(specifications.get("EAN"), Some("8710103974734"));
+    assert_eq!(specifications.get("Hoogte"), Some("25,6 cm"));
+
+    assert_snapshot("bol_specifications", &specifications);
+}
+
+#[test]
+fn bol_enrich_product() {
+    let listing = fixture_dom("bol/listing.html");
+    let mut product = Bol
+        .parse_listing(&state(), BOL_URL, &listing)
+        .unwrap()
+        .products
+        .remove(0);
+
+    let doc = fixture_dom("bol/product.html");
+    Bol.enrich_product(&mut product, &doc).unwrap();
+
+    assert_snapshot("bol_product", &product);
+}
+
+#[test]
+fn amazon_listing() {
+    let doc = fixture_dom("amazon/listing.html");
+    let listing = Amazon.parse_listing(&state(), AMAZON_URL, &doc).unwrap();
+
+    assert_snapshot("amazon_listing", &listing);
+}
+
+#[test]
+fn amazon_listing_sponsored() {
+    let listing = fixture("amazon/listing.html");
+    let doc = Html::parse_document(&listing);
+    let listing = Amazon.parse_listing(&state(), AMAZON_URL, &doc).unwrap();
+
+    let sponsored = &listing.products[0];
+    assert!(sponsored.sponsored);
+    // the tracking link is replaced by the product page it leads to
+    assert_eq!(
+        sponsored.url,
+        "https://amazon.nl/Oclean-X-Pro-Elektrische-Tandenborstel/dp/B0BX3YQ9XG/"
+    );
+    assert!(listing.products[1..].iter().all(|p| !p.sponsored));
+
+    // without the label the ad is still recognised by its markup
+    let unlabeled = fixture("amazon/listing.html").replace("puis-sponsored-label-text", "");
+    let doc = Html::parse_document(&unlabeled);
+    let listing = Amazon.parse_listing(&state(), AMAZON_URL, &doc).unwrap();
+    assert!(listing.products[0].sponsored);
+}
+
+#[test]
+fn bol_product_page_without_specifications() {
+    let doc = fixture_dom("bol/listing.html");
+    let res = bol::parse_product_page(&doc);
+
+    assert!(
+        matches!(res, Err(ScrapeError::LayoutChanged { selector }) if selector == "specs_container")
+    );
+}
+
+#[test]
+fn amazon_product_page() {
+    let mut product = Product {
+        url:
+            "https://amazon.nl/Philips-Sonicare-ProtectiveClean-HX6800-63/dp/B09NXCHZ61/ref=sr_1_2"
+                .into(),
+        ..Default::default()
+    };
+
+    let doc = fixture_dom("amazon/product.html");
+    Amazon.enrich_product(&mut product, &doc).unwrap();
+
+    assert_eq!(product.sku.as_deref(), Some("B09NXCHZ61"));
+    assert_eq!(
+        product.ean.as_ref().map(Gtin::as_str),
+        Some("8710103974734")
+    );
+    assert_eq!(product.brand.as_deref(), Some("Philips"));
+    assert_eq!(product.features.len(), 3);
+    assert_snapshot("amazon_product", &product);
+}
+
+#[test]
+fn amazon_product_page_unexpected_layout() {
+    let mut product = Product::default();
+    let doc = fixture_dom("amazon/listing.html");
+    let res = Amazon.enrich_product(&mut product, &doc);
+
+    assert!(matches!(res, Err(ScrapeError::LayoutChanged { .. })));
+}
+
+#[test]
+fn amazon_page_count() {
+    let doc = fixture_dom("amazon/listing.html");
+    assert_eq!(Amazon.page_count(&doc), Some(20));
+
+    // without pagination the amount of pages is derived from the results count
+    let doc = Html::parse_document(
+        r#"<span data-component-type="s-result-info-bar"><span>1-48 van meer dan 3.000 resultaten voor</span></span>"#,
+    );
+    assert_eq!(Amazon.page_count(&doc), Some(63));
+}
+
+#[test]
+fn amazon_captcha() {
+    let doc = fixture_dom("amazon/captcha.html");
+    assert!(Amazon.is_blocked(&doc));
+
+    let doc = fixture_dom("amazon/listing.html");
+    assert!(!Amazon.is_blocked(&doc));
+}
+
+#[test]
+fn amazon_listing_unexpected_layout() {
+    let doc = fixture_dom("bol/listing.html");
+    let res = Amazon.parse_listing(&state(), AMAZON_URL, &doc);
+
+    assert!(matches!(res, Err(ScrapeError::LayoutChanged { .. })));
+}
+
+#[test]
+fn schema_org_listing() {
+    let doc = fixture_dom("schema_org/listing.html");
+    let listing = SchemaOrg.parse_listing(&state(), SHOP_URL, &doc).unwrap();
+
+    // links to the same product, categories and other shops are skipped
+    let urls = listing
+        .products
+        .iter()
+        .map(|p| p.url.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        urls,
+        [
+            "https://www.fietsenwinkel.example/product/abus-hyban-2-0-fietshelm-zwart/",
+            "https://www.fietsenwinkel.example/product/lazer-cameleon-fietshelm/",
+            "https://www.fietsenwinkel.example/product/abus-bordo-6000-vouwslot/",
+        ]
+    );
+}
+
+#[test]
+fn schema_org_product_without_structured_data() {
+    let doc = fixture_dom("schema_org/listing.html");
+    let res = SchemaOrg.enrich_product(&mut Product::default(), &doc);
+
+    assert!(matches!(res, Err(ScrapeError::LayoutChanged { .. })));
+}
+
+#[test]
+fn amazon_marketplaces() {
+    for (url, domain) in [
+        ("https://www.amazon.nl/s?k=tandenborstel", "nl"),
+        ("https://www.amazon.de/s?k=zahnbuerste", "de"),
+        ("https://www.amazon.fr/s?k=brosse", "fr"),
+        ("https://www.amazon.com.be/s?k=tandenborstel", "com.be"),
+        ("https://www.amazon.co.uk/s?k=toothbrush", "co.uk"),
+        ("https://www.amazon.com/s?k=toothbrush", "com"),
+    ] {
+        assert!(Amazon.matches_url(url), "{url}");
+        assert_eq!(Marketplace::from_url(url).unwrap().domain, domain);
+    }
+
+    assert!(!Amazon.matches_url("https://www.amazon.es/s?k=cepillo"));
+    assert!(!Amazon.matches_url("https://www.amazon.co.uk.example/s"));
+}
+
+#[test]
+fn amazon_listing_fr() {
+    let doc = fixture_dom("amazon/listing_fr.html");
+    let url = "https://www.amazon.fr/s?k=brosse+a+dents+electrique";
+    let listing = Amazon.parse_listing(&state(), url, &doc).unwrap();
+
+    // the sponsored result comes first
+    assert_eq!(listing.products.len(), 3);
+    assert!(listing.failures.is_empty());
+    assert!(listing.products[0].sponsored);
+
+    let products = &listing.products[1..];
+    assert!(products[0].url.starts_with("https://amazon.fr/"));
+    assert_eq!(products[0].price.to_string(), "€ 47.49");
+    assert_eq!(
+        products[0].list_price.map(|p| p.to_string()).as_deref(),
+        Some("€ 84.99")
+    );
+    assert_eq!(products[1].price.to_string(), "€ 1049.00");
+    assert_eq!(Amazon.page_count(&doc), Some(63));
+}
+
+#[test]
+fn amazon_listing_uk() {
+    let doc = fixture_dom("amazon/listing_uk.html");
+    let url = "https://www.amazon.co.uk/s?k=electric+toothbrush";
+    let listing = Amazon.parse_listing(&state(), url, &doc).unwrap();
+
+    assert_eq!(listing.products.len(), 3);
+    assert!(listing.failures.is_empty());
+    assert!(listing
+        .products
+        .iter()
+        .all(|p| p.price.currency == Currency::Gbp));
+
+    let products = &listing.products[1..];
+    assert!(products[0].url.starts_with("https://amazon.co.uk/"));
+    assert_eq!(
+        products[0].discount().map(|d| d.to_string()).as_deref(),
+        Some("50.01")
+    );
+    assert_eq!(products[1].price.to_string(), "£ 1049.99");
+}
+
+#[test]
+fn bol_locales() {
+    for (url, locale) in [
+        ("https://www.bol.com/nl/nl/l/tandenborstels/12477/", "nl-NL"),
+        ("https://www.bol.com/be/nl/l/tandenborstels/12477/", "nl-BE"),
+        (
+            "https://www.bol.com/be/fr/l/brosses-a-dents/12477/",
+            "fr-BE",
+        ),
+        ("https://www.bol.com/", "nl-NL"),
+    ] {
+        assert_eq!(bol::locale_from_url(url).to_string(), locale);
+    }
+
+    let doc = fixture_dom("bol/listing.html");
+    let url = "https://www.bol.com/be/fr/l/brosses-a-dents-electriques/12477/";
+    let listing = Bol.parse_listing(&state(), url, &doc).unwrap();
+    assert!(listing
+        .products
+        .iter()
+        .all(|p| p.locale.map(|l| l.to_string()).as_deref() == Some("fr-BE")));
+}
+
+#[test]
+fn bol_product_page_fr() {
+    let doc = fixture_dom("bol/product_fr.html");
+    let mut product = Product {
+        url: "https://www.bol.com/be/fr/p/philips-sonicare-protectiveclean-4300-hx6800-63/9300000134521378/".into(),
+        ..Default::default()
+    };
+    Bol.enrich_product(&mut product, &doc).unwrap();
+
+    assert_eq!(
+        product.ean.as_ref().map(Gtin::as_str),
+        Some("8710103974734")
+    );
+    assert_eq!(product.brand.as_deref(), Some("Philips"));
+    assert_eq!(
+        product.locale.map(|l| l.to_string()).as_deref(),
+        Some("fr-BE")
+    );
+    assert_eq!(
+        product.specifications[2].section,
+        "Informations complémentaires"
+    );
+}
+
+#[test]
+fn ratings() {
+    let doc = fixture_dom("bol/listing.html");
+    let listing = Bol.parse_listing(&state(), BOL_URL, &doc).unwrap();
+    assert_eq!(listing.products[0].rating, Some(4.6));
+    assert_eq!(listing.products[0].review_count, Some(1893));
+
+    let doc = fixture_dom("amazon/listing.html");
+    let listing = Amazon.parse_listing(&state(), AMAZON_URL, &doc).unwrap();
+    assert_eq!(listing.products[0].rating, Some(4.4));
+    assert_eq!(listing.products[0].review_count, Some(2104));
+
+    let doc = fixture_dom("amazon/listing_uk.html");
+    let url = "https://www.amazon.co.uk/s?k=electric+toothbrush";
+    let listing = Amazon.parse_listing(&state(), url, &doc).unwrap();
+    assert_eq!(listing.products[1].rating, Some(4.6));
+    assert_eq!(listing.products[1].review_count, Some(31207));
+    assert_eq!(listing.products[2].rating, None);
+}
+
+#[test]
+fn ratings_from_product_page() {
+    let mut product = Product::default();
+    Amazon
+        .enrich_product(&mut product, &fixture_dom("amazon/product.html"))
+        .unwrap();
+    assert_eq!(product.rating, Some(4.6));
+    assert_eq!(product.review_count, Some(12873));
+
+    // ratings found on the listing are kept
+    let mut product = Product {
+        rating: Some(4.5),
+        review_count: Some(1900),
+        ..Default::default()
+    };
+    Bol.enrich_product(&mut product, &fixture_dom("bol/product.html"))
+        .unwrap();
+    assert_eq!(product.rating, Some(4.5));
+    assert_eq!(product.review_count, Some(1900));
+
+    let mut product = Product::default();
+    Bol.enrich_product(&mut product, &fixture_dom("bol/product_fr.html"))
+        .unwrap();
+    assert_eq!(product.rating, None);
+}
+
+#[test]
+fn bol_offer() {
+    let doc = fixture_dom("bol/listing.html");
+    let listing = Bol.parse_listing(&state(), BOL_URL, &doc).unwrap();
+
+    let product = &listing.products[0];
+    assert_eq!(product.availability, Some(Availability::InStock));
+    assert_eq!(
+        product.delivery.as_ref().map(|d| d.text.as_str()),
+        Some("Voor 23:59 besteld, morgen in huis")
+    );
+    assert_eq!(product.seller.as_deref(), Some("bol"));
+    assert_eq!(product.sold_by_platform, Some(true));
+
+    let partner = &listing.products[1];
+    assert_eq!(partner.seller.as_deref(), Some("Tandartswinkel"));
+    assert_eq!(partner.sold_by_platform, Some(false));
+
+    let sold_out = &listing.products[2];
+    assert_eq!(sold_out.availability, Some(Availability::OutOfStock));
+    assert!(sold_out.delivery.is_none());
+
+    let mut product = Product::default();
+    Bol.enrich_product(&mut product, &fixture_dom("bol/product_fr.html"))
+        .unwrap();
+    assert_eq!(product.availability, Some(Availability::InStock));
+    assert_eq!(product.seller.as_deref(), Some("bol"));
+}
+
+#[test]
+fn amazon_offer() {
+    let doc = fixture_dom("amazon/listing.html");
+    let listing = Amazon.parse_listing(&state(), AMAZON_URL, &doc).unwrap();
+    let product = &listing.products[1];
+    assert_eq!(product.availability, Some(Availability::InStock));
+    assert_eq!(
+        product.delivery.as_ref().and_then(|d| d.estimate),
+        Some(DeliveryEstimate::Date { month: 10, day: 20 })
+    );
+
+    let mut product = Product::default();
+    Amazon
+        .enrich_product(&mut product, &fixture_dom("amazon/product.html"))
+        .unwrap();
+    assert_eq!(product.availability, Some(Availability::InStock));
+    assert_eq!(
+        product.delivery.map(|d| d.text).as_deref(),
+        Some("GRATIS bezorging zondag 20 oktober")
+    );
+    assert_eq!(product.seller.as_deref(), Some("Amazon"));
+    assert_eq!(product.sold_by_platform, Some(true));
+}
+
+#[test]
+fn categories() {
+    let mut product = Product::default();
+    Bol.enrich_product(&mut product, &fixture_dom("bol/product.html"))
+        .unwrap();
+    assert_eq!(
+        product.categories,
+        [
+            "Mooi & Gezond",
+            "Mondverzorging",
+            "Elektrische tandenborstels"
+        ]
+    );
+    assert_eq!(
+        product.category_path(),
+        "Mooi & Gezond > Mondverzorging > Elektrische tandenborstels"
+    );
+
+    let mut product = Product::default();
+    Amazon
+        .enrich_product(&mut product, &fixture_dom("amazon/product.html"))
+        .unwrap();
+    assert_eq!(
+        product.categories,
+        [
+            "Gezondheid & persoonlijke verzorging",
+            "Mondverzorging",
+            "Elektrische tandenborstels"
+        ]
+    );
+
+    // pages without breadcrumbs leave the categories empty
+    let mut product = Product::default();
+    Bol.enrich_product(&mut product, &fixture_dom("bol/product_fr.html"))
+        .unwrap();
+    assert!(product.categories.is_empty());
+}
@@ -0,0 +1,397 @@
+mod common;
+
+use common::*;
+use reqwest::StatusCode;
+use scraping::{
+    error::{Result, ScrapeError},
+    fetch::{FileFetcher, MockFetcher, Response},
+    providers::{
+        amazon::Amazon, bol::Bol, page_from_url, paginate_url, Gtin, Html, Listing, Pages, Product,
+        Provider, Registry, ShopProvider, Stage,
+    },
+    status,
+};
+use std::sync::Arc;
+
+const BOL_PRODUCT_URL: &str =
+    "https://bol.com/nl/nl/p/philips-sonicare-protectiveclean-4300-hx6800-63/9300000134521378/";
+
+#[tokio::test]
+async fn bol_query_products() {
+    let fetcher = MockFetcher::new()
+        .respond(
+            format!("{BOL_URL}?page=1"),
+            Response::ok(fixture("bol/listing.html")),
+        )
+        .respond(BOL_PRODUCT_URL, Response::ok(fixture("bol/product.html")));
+    let fetcher = Arc::new(fetcher);
+    let state = state();
+
+    let provider = Provider::from_url(BOL_URL).unwrap();
+    let result = provider
+        .query_products(BOL_URL, 1, fetcher.clone(), state.clone())
+        .await;
+
+    let products = &result.products;
+    assert_eq!(products.len(), 3);
+    assert_eq!(
+        products[0].ean.as_ref().map(Gtin::as_str),
+        Some("8710103974734")
+    );
+    // the other product pages are not served so these are left as is
+    assert!(products[1..].iter().all(|p| p.ean.is_none()));
+
+    // listing page and a detail page for every product
+    assert_eq!(fetcher.requests().len(), 4);
+    assert_eq!(state.queries().success.load(), 2);
+    assert_eq!(state.queries().errored.load(), 2);
+
+    let stages = result.failures.iter().map(|f| f.stage).collect::<Vec<_>>();
+    assert_eq!(stages, [Stage::ProductPage, Stage::ProductPage]);
+    assert_eq!(result.failures[0].url, products[1].url);
+}
+
+#[tokio::test]
+async fn bol_query_products_keeps_successful_pages() {
+    let fetcher = MockFetcher::new().respond(
+        format!("{BOL_URL}?page=2"),
+        Response::ok(fixture("bol/listing.html")),
+    );
+
+    let provider = Provider::from_url(BOL_URL).unwrap();
+    let result = provider
+        .query_products(BOL_URL, 2, Arc::new(fetcher), state())
+        .await;
+
+    assert_eq!(result.products.len(), 3);
+
+    let failure = &result.failures[0];
+    assert_eq!(failure.url, format!("{BOL_URL}?page=1"));
+    assert_eq!(failure.stage, Stage::ListingPage);
+    assert!(matches!(
+        failure.error,
+        ScrapeError::Status {
+            status: StatusCode::NOT_FOUND,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn bol_query_all_pages() {
+    let fetcher = MockFetcher::new().respond(
+        format!("{BOL_URL}?page=1"),
+        Response::ok(fixture("bol/listing.html")),
+    );
+    let fetcher = Arc::new(fetcher);
+
+    let provider = Provider::from_url(BOL_URL).unwrap();
+    provider
+        .query_products(BOL_URL, Pages::All, fetcher.clone(), state())
+        .await;
+
+    // "1.234 resultaten" with 24 results per page
+    let requests = fetcher.requests();
+    let listing_requests = requests.iter().filter(|url| url.starts_with(BOL_URL));
+    assert_eq!(listing_requests.count(), 52);
+}
+
+#[tokio::test]
+async fn bol_query_all_pages_until_empty() {
+    let fetcher = MockFetcher::new()
+        .respond(
+            format!("{BOL_URL}?page=1"),
+            Response::ok(fixture("bol/listing_duplicates.html")),
+        )
+        .respond(
+            format!("{BOL_URL}?page=2"),
+            Response::ok(fixture("bol/listing_duplicates.html")),
+        );
+    let fetcher = Arc::new(fetcher);
+
+    let provider = Provider::from_url(BOL_URL).unwrap();
+    let result = provider
+        .query_products(BOL_URL, Pages::All, fetcher.clone(), state())
+        .await;
+
+    assert_eq!(result.products.len(), 2);
+    let requests = fetcher.requests();
+    assert!(requests.contains(&format!("{BOL_URL}?page=3")));
+    assert!(!requests.contains(&format!("{BOL_URL}?page=4")));
+}
+
+#[tokio::test]
+async fn amazon_query_page_range() {
+    let fetcher = Arc::new(MockFetcher::new());
+
+    let provider = Provider::from_url(AMAZON_URL).unwrap();
+    let pages = Pages::Range {
+        first: 11,
+        last: 13,
+    };
+    provider
+        .query_products(AMAZON_URL, pages, fetcher.clone(), state())
+        .await;
+
+    let mut requests = fetcher.requests();
+    requests.sort();
+    assert_eq!(
+        requests,
+        [11, 12, 13].map(|page| format!("{AMAZON_URL}&page={page}"))
+    );
+}
+
+#[tokio::test]
+async fn bol_query_products_starts_at_page_in_url() {
+    let fetcher = Arc::new(MockFetcher::new());
+    let url = format!("{BOL_URL}?page=11&sort=rnk");
+
+    let provider = Provider::from_url(&url).unwrap();
+    provider
+        .query_products(&url, 2, fetcher.clone(), state())
+        .await;
+
+    let mut requests = fetcher.requests();
+    requests.sort();
+    assert_eq!(
+        requests,
+        [
+            format!("{BOL_URL}?page=11&sort=rnk"),
+            format!("{BOL_URL}?page=12&sort=rnk"),
+        ]
+    );
+}
+
+/// Shop whose parser crashes on the second page
+struct CrashingShop;
+
+impl ShopProvider for CrashingShop {
+    fn matches_url(&self, _url: &str) -> bool {
+        true
+    }
+
+    fn parse_listing(&self, _state: &status::State, url: &str, _doc: &Html) -> Result<Listing> {
+        assert!(!url.ends_with("page=2"), "unexpected page");
+        Ok(Listing {
+            products: vec![Product {
+                url: url.to_string(),
+                ..Default::default()
+            }],
+            failures: Vec::new(),
+        })
+    }
+}
+
+#[tokio::test]
+async fn query_page_panics() {
+    let fetcher = MockFetcher::new()
+        .respond(format!("{SHOP_URL}?page=1"), Response::ok(""))
+        .respond(format!("{SHOP_URL}?page=2"), Response::ok(""));
+
+    let provider = Provider::new(CrashingShop);
+    let result = provider
+        .query_products(SHOP_URL, 2, Arc::new(fetcher), state())
+        .await;
+
+    assert_eq!(result.products.len(), 1);
+    let failure = &result.failures[0];
+    assert_eq!(failure.url, format!("{SHOP_URL}?page=2"));
+    assert_eq!(failure.stage, Stage::ListingPage);
+    assert!(matches!(failure.error, ScrapeError::Panicked { .. }));
+}
+
+#[test]
+fn paginate() {
+    assert_eq!(paginate_url(BOL_URL, 2), format!("{BOL_URL}?page=2"));
+    assert_eq!(paginate_url(AMAZON_URL, 2), format!("{AMAZON_URL}&page=2"));
+    assert_eq!(
+        paginate_url(&format!("{AMAZON_URL}&page=5&ref=sr_pg_5"), 6),
+        format!("{AMAZON_URL}&page=6&ref=sr_pg_5")
+    );
+    // parameters that merely end with `page` are left alone
+    assert_eq!(
+        paginate_url("https://www.bol.com/nl/nl/s/?subpage=3", 2),
+        "https://www.bol.com/nl/nl/s/?subpage=3&page=2"
+    );
+
+    assert_eq!(page_from_url(&format!("{BOL_URL}?page=11")), Some(11));
+    assert_eq!(page_from_url(BOL_URL), None);
+}
+
+#[tokio::test]
+async fn amazon_query_products_blocked() {
+    let fetcher = MockFetcher::new().respond(
+        format!("{AMAZON_URL}&page=1"),
+        Response::ok(fixture("amazon/captcha.html")),
+    );
+
+    let provider = Provider::from_url(AMAZON_URL).unwrap();
+    let result = provider
+        .query_products(AMAZON_URL, 1, Arc::new(fetcher), state())
+        .await;
+
+    assert!(result.products.is_empty());
+    assert!(matches!(
+        result.failures[0].error,
+        ScrapeError::Blocked { .. }
+    ));
+}
+
+#[tokio::test]
+async fn amazon_query_products_with_details() {
+    let product_url =
+        "https://amazon.nl/Philips-Sonicare-ProtectiveClean-HX6800-63/dp/B09NXCHZ61/ref=sr_1_2";
+    let fetcher = MockFetcher::new()
+        .respond(
+            format!("{AMAZON_URL}&page=1"),
+            Response::ok(fixture("amazon/listing.html")),
+        )
+        .respond(product_url, Response::ok(fixture("amazon/product.html")));
+    let state = state();
+
+    let provider = Provider::from_url(AMAZON_URL).unwrap();
+    let result = provider
+        .query_products(AMAZON_URL, 1, Arc::new(fetcher), state.clone())
+        .await;
+
+    let product = &result.products[0];
+    assert_eq!(product.url, product_url);
+    assert_eq!(product.sku.as_deref(), Some("B09NXCHZ61"));
+    assert_eq!(
+        product.ean.as_ref().map(Gtin::as_str),
+        Some("8710103974734")
+    );
+    assert_eq!(state.queries().success.load(), 2);
+}
+
+#[tokio::test]
+async fn amazon_query_products() {
+    let fetcher = FileFetcher::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .route(format!("{AMAZON_URL}&page=1"), "amazon/listing.html")
+        .route(format!("{AMAZON_URL}&page=2"), "amazon/listing.html");
+    let state = state();
+
+    let provider = Provider::from_url(AMAZON_URL).unwrap();
+    let result = provider
+        .query_products(AMAZON_URL, 2, Arc::new(fetcher), state.clone())
+        .await;
+
+    // every page is parsed on its own, so the same listing served twice yields its products twice
+    assert_eq!(result.products.len(), 4);
+    assert_eq!(state.queries().success.load(), 2);
+}
+
+#[tokio::test]
+async fn amazon_query_products_sponsored() {
+    let fetcher = FileFetcher::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"))
+        .route(format!("{AMAZON_URL}&page=1"), "amazon/listing.html");
+    let fetcher = Arc::new(fetcher);
+
+    let provider = Provider::from_url(AMAZON_URL).unwrap();
+    let result = provider
+        .clone()
+        .query_products(AMAZON_URL, 1, fetcher.clone(), state())
+        .await;
+    assert!(result.products.iter().all(|p| !p.sponsored));
+
+    let result = provider
+        .include_sponsored(true)
+        .query_products(AMAZON_URL, 1, fetcher, state())
+        .await;
+    assert_eq!(result.products.iter().filter(|p| p.sponsored).count(), 1);
+}
+
+#[test]
+fn unsupported_url() {
+    for url in ["bol.com", "ftp://ftp.example/producten"] {
+        assert!(matches!(
+            Provider::from_url(url),
+            Err(ScrapeError::UnsupportedUrl { .. })
+        ));
+    }
+
+    // only the generic provider accepts other shops
+    let mut registry = Registry::new();
+    registry.register(Amazon).register(Bol);
+    let res = registry.from_url("https://www.coolblue.nl/zoeken?query=tandenborstel");
+    assert!(matches!(res, Err(ScrapeError::UnsupportedUrl { .. })));
+    assert!(Provider::from_url("https://www.coolblue.nl/zoeken?query=tandenborstel").is_ok());
+    // countries of the builtin shops without their own provider aren't left to the generic one
+    assert!(matches!(
+        Provider::from_url("https://www.amazon.es/s?k=cepillo"),
+        Err(ScrapeError::UnsupportedUrl { .. })
+    ));
+}
+
+const SHOP_PRODUCT_URL: &str = "https://www.fietsenwinkel.example/product/";
+
+fn shop_fetcher() -> MockFetcher {
+    MockFetcher::new()
+        .respond(
+            format!("{SHOP_URL}?page=1"),
+            Response::ok(fixture("schema_org/listing.html")),
+        )
+        .respond(
+            format!("{SHOP_PRODUCT_URL}abus-hyban-2-0-fietshelm-zwart/"),
+            Response::ok(fixture("structured/microdata.html")),
+        )
+        .respond(
+            format!("{SHOP_PRODUCT_URL}lazer-cameleon-fietshelm/"),
+            Response::ok(fixture("schema_org/product.html")),
+        )
+}
+
+#[tokio::test]
+async fn schema_org_query_products() {
+    let fetcher = Arc::new(shop_fetcher());
+    let provider = Provider::from_url(SHOP_URL).unwrap();
+    let result = provider
+        .query_products(SHOP_URL, 1, fetcher.clone(), state())
+        .await;
+
+    // the vouwslot page isn't served, with only its link it's left out
+    assert_eq!(result.products.len(), 2);
+    assert!(result.products.iter().all(|p| !p.title.is_empty()));
+    assert_eq!(result.failures.len(), 1);
+    assert_eq!(result.failures[0].stage, Stage::ProductPage);
+    assert_eq!(fetcher.requests().len(), 4);
+    assert_snapshot("schema_org_products", &result.products);
+}
+
+#[tokio::test]
+async fn query_product_urls() {
+    let urls = ["lazer-cameleon-fietshelm/", "abus-bordo-6000-vouwslot/"]
+        .map(|path| format!("{SHOP_PRODUCT_URL}{path}"));
+    let provider = Provider::from_url(&urls[0]).unwrap();
+    let result = provider
+        .query_product_urls(&urls, Arc::new(shop_fetcher()), state())
+        .await;
+
+    assert_eq!(result.products.len(), 1);
+    assert_eq!(result.products[0].title, "Lazer Cameleon fietshelm");
+    assert_eq!(
+        result.products[0].ean.as_ref().map(Gtin::as_str),
+        Some("5420078850348")
+    );
+    assert_eq!(result.failures.len(), 1);
+    assert_eq!(result.failures[0].url, urls[1]);
+}
+
+#[tokio::test]
+async fn query_product_urls_keeps_products_without_ean() {
+    let page = fixture("bol/product_structured.html").replace(r#""gtin13": "4210201290674","#, "");
+    let fetcher = MockFetcher::new().respond(BOL_PRODUCT_URL, Response::ok(page));
+    let urls = [BOL_PRODUCT_URL.to_string()];
+    let provider = Provider::from_url(BOL_PRODUCT_URL).unwrap();
+    let result = provider
+        .query_product_urls(&urls, Arc::new(fetcher), state())
+        .await;
+
+    // the page filled in the product, only the EAN is missing
+    assert_eq!(result.products.len(), 1);
+    assert_eq!(
+        result.products[0].title,
+        "Oral-B Pro 3 3000 - Elektrische tandenborstel"
+    );
+    assert_eq!(result.failures.len(), 1);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::*;
+use futures::future::BoxFuture;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use scraping::{
+    error::{Result, ScrapeError},
+    fetch::{Fetcher, MockFetcher, Response},
+    retry::{RetryFetcher, RetryPolicy},
+};
+use std::time::Duration;
+use tokio::time::Instant;
+
+const URL: &str = "https://www.bol.com/nl/nl/l/elektrische-tandenborstels/12477/";
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        base_delay: Duration::from_millis(1),
+        ..Default::default()
+    }
+}
+
+fn unavailable() -> Response {
+    Response::new(StatusCode::SERVICE_UNAVAILABLE, "")
+}
+
+#[tokio::test]
+async fn retries_until_success() {
+    let mock = MockFetcher::new()
+        .respond(URL, unavailable())
+        .respond(URL, unavailable())
+        .respond(URL, Response::ok("ok"));
+    let state = state();
+    let fetcher = RetryFetcher::new(mock, policy(), state.clone());
+
+    let res = fetcher.fetch(URL).await.unwrap();
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(state.queries().retries.load(), 2);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let mock = MockFetcher::new().respond(URL, unavailable());
+    let state = state();
+    let fetcher = RetryFetcher::new(mock, policy(), state.clone());
+
+    let res = fetcher.fetch(URL).await.unwrap();
+
+    assert_eq!(res.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(state.queries().retries.load(), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let state = state();
+    let fetcher = RetryFetcher::new(MockFetcher::new(), policy(), state.clone());
+
+    let res = fetcher.fetch(URL).await.unwrap();
+
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(state.queries().retries.load(), 0);
+}
+
+// the clock is paused and only advances while every task waits, so the timing is exact
+#[tokio::test(start_paused = true)]
+async fn honors_retry_after() {
+    let mut throttled = Response::new(StatusCode::TOO_MANY_REQUESTS, "");
+    throttled.headers.insert(RETRY_AFTER, "1".parse().unwrap());
+    let mock = MockFetcher::new()
+        .respond(URL, throttled)
+        .respond(URL, Response::ok("ok"));
+    let fetcher = RetryFetcher::new(mock, policy(), state());
+
+    let start = Instant::now();
+    let res = fetcher.fetch(URL).await.unwrap();
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(start.elapsed(), Duration::from_secs(1));
+}
+
+#[test]
+fn backoff_grows_exponentially() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_millis(350),
+        jitter: 0.0,
+        ..Default::default()
+    };
+
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(200));
+    assert_eq!(policy.backoff(3), Duration::from_millis(350));
+}
+
+#[test]
+fn backoff_jitter_stays_within_bounds() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        jitter: 0.5,
+        ..Default::default()
+    };
+
+    for _ in 0..100 {
+        let delay = policy.backoff(1);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+}
+
+/// Fetcher that always fails with the error made by `error`
+struct FailingFetcher {
+    error: fn() -> ScrapeError,
+}
+
+impl Fetcher for FailingFetcher {
+    fn fetch<'a>(&'a self, _url: &'a str) -> BoxFuture<'a, Result<Response>> {
+        Box::pin(async move { Err((self.error)()) })
+    }
+}
+
+#[tokio::test]
+async fn retries_transient_errors_only() {
+    let network = FailingFetcher {
+        error: || ScrapeError::network(URL, "connection reset"),
+    };
+    let state = state();
+    let fetcher = RetryFetcher::new(network, policy(), state.clone());
+    assert!(fetcher.fetch(URL).await.is_err());
+    assert_eq!(state.queries().retries.load(), 3);
+
+    let blocked = FailingFetcher {
+        error: || ScrapeError::Blocked { url: URL.into() },
+    };
+    let state = common::state();
+    let fetcher = RetryFetcher::new(blocked, policy(), state.clone());
+    assert!(fetcher.fetch(URL).await.is_err());
+    assert_eq!(state.queries().retries.load(), 0);
+}
@@ -0,0 +1,86 @@
+use scraping::{error::DefinitionsError, selectors::Definitions};
+
+#[test]
+fn defaults_are_valid() {
+    let definitions = Definitions::defaults();
+
+    assert!(definitions.shop("bol").count() > 0);
+    assert!(definitions.shop("amazon").count() > 0);
+    assert_eq!(definitions.shop("coolblue").count(), 0);
+}
+
+#[test]
+fn merge_overrides_selectors() {
+    let mut definitions = Definitions::defaults();
+    definitions
+        .merge("[bol]\ntitle = '.product-card__title'\n")
+        .unwrap();
+
+    let (_, title) = definitions
+        .shop("bol")
+        .find(|(name, _)| *name == "title")
+        .unwrap();
+    assert_eq!(title.source, ".product-card__title");
+
+    // other selectors keep their defaults
+    let (_, price) = definitions
+        .shop("bol")
+        .find(|(name, _)| *name == "price")
+        .unwrap();
+    assert_eq!(price.source, r#"meta[itemprop="price"]"#);
+}
+
+#[test]
+fn merge_rejects_invalid_definitions() {
+    let mut definitions = Definitions::defaults();
+
+    let res = definitions.merge("[coolblue]\ntitle = 'h1'\n");
+    assert!(matches!(res, Err(DefinitionsError::UnknownShop { .. })));
+
+    let res = definitions.merge("[bol]\nproduct_title = 'h1'\n");
+    assert!(matches!(res, Err(DefinitionsError::UnknownSelector { .. })));
+
+    let res =
+        definitions.merge("[bol]\ntitle = '.product-card__title'\nprice = 'meta[itemprop='\n");
+    assert!(matches!(res, Err(DefinitionsError::InvalidSelector { .. })));
+
+    let res = definitions.merge("[bol\ntitle = 'h1'\n");
+    assert!(matches!(res, Err(DefinitionsError::Toml(_))));
+
+    // nothing is changed when part of the definitions is invalid
+    let (_, title) = definitions
+        .shop("bol")
+        .find(|(name, _)| *name == "title")
+        .unwrap();
+    assert_eq!(title.source, ".product-title");
+}
+
+#[test]
+fn merge_known_skips_unknown_definitions() {
+    let mut definitions = Definitions::defaults();
+
+    // published for a newer release that knows more selectors and shops
+    let latest = r#"
+        [bol]
+        title = ".product-card__title"
+        product_badge = ".badge"
+
+        [coolblue]
+        title = "h1"
+    "#;
+    let skipped = definitions.merge_known(latest).unwrap();
+    assert_eq!(skipped.len(), 2);
+    assert!(skipped.contains(&"bol.product_badge".to_string()));
+    assert!(skipped.contains(&"coolblue".to_string()));
+
+    // the known selectors are still applied
+    let (_, title) = definitions
+        .shop("bol")
+        .find(|(name, _)| *name == "title")
+        .unwrap();
+    assert_eq!(title.source, ".product-card__title");
+
+    // invalid selectors still reject everything
+    let res = definitions.merge_known("[bol]\nprice = 'meta[itemprop='\n");
+    assert!(matches!(res, Err(DefinitionsError::InvalidSelector { .. })));
+}
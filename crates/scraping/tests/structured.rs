@@ -0,0 +1,118 @@
+mod common;
+
+use common::*;
+use scraping::{
+    error::ScrapeError,
+    providers::{bol::Bol, Gtin, Html, Product, ShopProvider},
+    structured,
+};
+
+#[test]
+fn json_ld_product() {
+    let doc = fixture_dom("bol/product.html");
+    let product = structured::product(&doc).unwrap();
+
+    assert_eq!(product.gtin.as_deref(), Some("8710103974734"));
+    assert_eq!(product.brand.as_deref(), Some("Philips"));
+    assert_eq!(
+        product.price.map(|p| p.to_string()).as_deref(),
+        Some("€ 54.99")
+    );
+    assert_eq!(product.availability.as_deref(), Some("InStock"));
+    assert_snapshot("structured_json_ld", &product);
+}
+
+#[test]
+fn json_ld_graph_and_broken_scripts() {
+    let doc = fixture_dom("bol/product_structured.html");
+    let products = structured::products(&doc);
+
+    assert_eq!(products.len(), 1);
+    assert_eq!(products[0].brand.as_deref(), Some("Oral-B"));
+    assert_eq!(products[0].price, "39.95".parse().ok());
+    assert_eq!(products[0].availability.as_deref(), Some("OutOfStock"));
+    // the description is HTML, only its text is kept
+    assert_eq!(
+        products[0].description.as_deref(),
+        Some("Elektrische tandenborstel met drukcontrole & timer.")
+    );
+}
+
+#[test]
+fn microdata_product() {
+    let doc = fixture_dom("structured/microdata.html");
+    let products = structured::products(&doc);
+
+    // the related product is a separate item, its properties don't leak into the main product
+    assert_eq!(products.len(), 2);
+    assert_eq!(
+        products[1].name.as_deref(),
+        Some("Abus Bordo 6000 vouwslot")
+    );
+    assert_eq!(products[1].price, "79.99".parse().ok());
+    assert_snapshot("structured_microdata", &products[0]);
+}
+
+#[test]
+fn bol_listing_falls_back_to_structured_data() {
+    let doc = fixture_dom("bol/listing_structured.html");
+    let state = state();
+    let listing = Bol.parse_listing(&state, BOL_URL, &doc).unwrap();
+
+    assert_eq!(listing.products.len(), 2);
+    assert_eq!(state.queries().duplicates.load(), 1);
+    assert_snapshot("bol_listing_structured", &listing);
+}
+
+#[test]
+fn bol_product_falls_back_to_structured_data() {
+    let doc = fixture_dom("bol/product_structured.html");
+    let mut product = Product {
+        title: "Oral-B Pro 3 3000".into(),
+        ..Default::default()
+    };
+    Bol.enrich_product(&mut product, &doc).unwrap();
+
+    assert_eq!(product.title, "Oral-B Pro 3 3000");
+    assert_eq!(
+        product.ean.as_ref().map(Gtin::as_str),
+        Some("4210201290674")
+    );
+    assert_eq!(product.sku.as_deref(), Some("9300000040817640"));
+    assert!(product.specifications.is_empty());
+}
+
+#[test]
+fn bol_product_without_structured_data() {
+    let page = fixture("bol/product_structured.html").replace("application/ld+json", "text/plain");
+    let doc = Html::parse_document(&page);
+    let res = Bol.enrich_product(&mut Product::default(), &doc);
+
+    assert!(matches!(res, Err(ScrapeError::LayoutChanged { .. })));
+}
+
+#[test]
+fn gtin_keeps_leading_zeros() {
+    let doc = fixture_dom("structured/upc.html");
+    let product = structured::product(&doc).unwrap().to_product().unwrap();
+
+    let ean = product.ean.unwrap();
+    assert_eq!(ean.as_str(), "0037000865841");
+    assert_eq!(ean.kind(), gtin::GtinKind::Gtin13);
+}
+
+#[test]
+fn breadcrumbs() {
+    let doc = fixture_dom("bol/listing_structured.html");
+    assert_eq!(structured::breadcrumbs(&doc), ["Mooi & Gezond"]);
+
+    // sorted by position, names can be on the linked page
+    let doc = fixture_dom("schema_org/product.html");
+    assert_eq!(
+        structured::breadcrumbs(&doc),
+        ["Fietsaccessoires", "Fietshelmen"]
+    );
+
+    let doc = fixture_dom("structured/microdata.html");
+    assert!(structured::breadcrumbs(&doc).is_empty());
+}
@@ -0,0 +1,73 @@
+use scraping::{offer::Delivery, providers::Product, text};
+
+#[test]
+fn normalize() {
+    assert_eq!(
+        text::normalize("\u{200e}Merk\u{200f} :  Philips "),
+        "Merk : Philips"
+    );
+    // a decomposed é is composed into a single character
+    assert_eq!(
+        text::normalize("Brosse a\u{301} dents"),
+        "Brosse \u{e1} dents"
+    );
+    // the text is already decoded, so nothing in it is taken for markup
+    assert_eq!(
+        text::normalize("Kabel <A4 formaat>\n x"),
+        "Kabel <A4 formaat> x"
+    );
+    assert_eq!(text::normalize("Zwart &amp; Blauw"), "Zwart &amp; Blauw");
+}
+
+#[test]
+fn html_to_text() {
+    assert_eq!(
+        text::html_to_text("Oral-B Pro 3 3000, Zwart &amp; Blauw"),
+        "Oral-B Pro 3 3000, Zwart & Blauw"
+    );
+    assert_eq!(
+        text::html_to_text("<span>Philips\n   Sonicare</span> <b>&quot;4300&quot;</b>"),
+        "Philips Sonicare \"4300\""
+    );
+    // a lone less than sign isn't markup
+    assert_eq!(text::html_to_text("maat < 40 cm"), "maat < 40 cm");
+}
+
+#[test]
+fn truncate() {
+    let text = "Verwijdert tot 7x meer tandplak dan een handtandenborstel";
+    assert_eq!(text::truncate(text, 100), text);
+    assert_eq!(text::truncate(text, 25), "Verwijdert tot 7x meer…");
+    assert_eq!(text::truncate(text, 24), "Verwijdert tot 7x meer…");
+    assert_eq!(text::truncate("Handtandenborstel", 8), "Handtan…");
+    assert!(text::truncate(text, 20).chars().count() <= 20);
+    assert_eq!(text::truncate(text, 1), "…");
+    assert_eq!(text::truncate(text, 0), "");
+}
+
+#[test]
+fn normalize_product() {
+    let mut product = Product {
+        title: "Zwart & Blauw\n".into(),
+        sku: Some("ORAL-B-PRO3-3000-ZWART-BLAUW".into()),
+        brand: Some(" Oral-B ".into()),
+        description: Some("Verwijdert tot 7x meer tandplak.\n\nMet druksensor.".into()),
+        features: vec!["  ".into(), " 2 opzetborstels".into()],
+        seller: Some("Tandenborstel\u{a0}Shop ".into()),
+        delivery: Some(Delivery::parse("Morgen  in huis\n")),
+        ..Default::default()
+    };
+    product.normalize_text(Some(20));
+
+    assert_eq!(product.title, "Zwart & Blauw");
+    // only free text is shortened
+    assert_eq!(product.sku.as_deref(), Some("ORAL-B-PRO3-3000-ZWART-BLAUW"));
+    assert_eq!(product.brand.as_deref(), Some("Oral-B"));
+    assert_eq!(product.description.as_deref(), Some("Verwijdert tot 7x…"));
+    assert_eq!(product.features, ["2 opzetborstels"]);
+    assert_eq!(product.seller.as_deref(), Some("Tandenborstel Shop"));
+    assert_eq!(
+        product.delivery.map(|delivery| delivery.text).as_deref(),
+        Some("Morgen in huis")
+    );
+}
@@ -1,3 +1,5 @@
+use anyhow::{bail, Result};
+use gtin::Gtin;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -74,8 +76,20 @@ struct Fulfilment {
 }
 
 impl Offer {
-    pub fn new(title: &str, ean: &str, price: f64, stock: i32, reference: Option<&str>) -> Self {
-        Self {
+    /// Fails when the EAN can't be written as the 13 digits bol expects
+    pub fn new(
+        title: &str,
+        ean: &Gtin,
+        price: f64,
+        stock: i32,
+        reference: Option<&str>,
+    ) -> Result<Self> {
+        // bol only knows 13 digit EANs, UPC codes are padded
+        let Some(ean) = ean.to_gtin13() else {
+            bail!("{ean} can't be offered on bol, it doesn't fit in 13 digits");
+        };
+
+        Ok(Self {
             ean: ean.to_string(),
             pricing: Pricing::new(price),
             condition: Condition::default(),
@@ -91,7 +105,7 @@ impl Offer {
                 // TODO:
                 delivery_code: "24uurs-23".into(),
             },
-        }
+        })
     }
 }
 
@@ -150,7 +164,22 @@ pub struct Asset {
 pub enum Language {
     #[default]
     Nl,
-    // NlBe,
-    // Fr,
-    // FrBe,
+    #[serde(rename = "nl-BE")]
+    NlBe,
+    Fr,
+    #[serde(rename = "fr-BE")]
+    FrBe,
+}
+
+impl Language {
+    /// Language of a scraped product's locale, e.g. `nl-BE`
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        match locale {
+            "nl" | "nl-NL" => Some(Self::Nl),
+            "nl-BE" => Some(Self::NlBe),
+            "fr" | "fr-FR" => Some(Self::Fr),
+            "fr-BE" => Some(Self::FrBe),
+            _ => None,
+        }
+    }
 }